@@ -1,9 +1,62 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::collections::BTreeMap;
+use std::fmt;
+use num_bigint::BigUint;
+
+// Which joltage steps an adapter (or the device) accepts from the one below it,
+// and how far above the highest adapter the device is rated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoltageRules {
+    steps: Vec<i64>,      // sorted, distinct, positive
+    device_offset: i64,
+}
+
+impl JoltageRules {
+    pub fn puzzle() -> JoltageRules {
+        return JoltageRules { steps: vec![1, 2, 3], device_offset: 3 };
+    }
+
+    pub fn new(steps: &[i64], device_offset: i64) -> Result<JoltageRules, String> {
+        let mut steps = steps.to_vec();
+        steps.sort();
+        steps.dedup();
+        if steps.is_empty() || steps[0] <= 0 {
+            return Err(format!("joltage steps must be positive, not {:?}", steps));
+        }
+        if device_offset <= 0 {
+            return Err(format!("device offset must be positive, not {}", device_offset));
+        }
+
+        return Ok(JoltageRules { steps: steps, device_offset: device_offset });
+    }
+
+    fn allows(&self, step: i64) -> bool {
+        return self.steps.binary_search(&step).is_ok();
+    }
+
+    fn max_step(&self) -> i64 {
+        return self.steps[self.steps.len()-1];
+    }
+}
+
+// A step in the full adapter chain that the rules don't allow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainError {
+    pub from: i64,
+    pub to: i64,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can't step from {} to {} jolts, a gap of {}", self.from, self.to, self.to - self.from)
+    }
+}
 
 pub struct AdapterArray {
     adapters: Vec<i64>,  // sorted vector of adapters
+    rules: JoltageRules,
 }
 
 impl AdapterArray {
@@ -21,47 +74,135 @@ impl AdapterArray {
         }
         adapters.sort();
 
-        return AdapterArray { adapters: adapters };
+        return AdapterArray { adapters: adapters, rules: JoltageRules::puzzle() };
     }
-}
 
-impl crate::Day for AdapterArray {
-    fn part1(&self) -> Result<i64, &str> {
-        let mut jolts: i64 = 0;
-        let mut step_1 = 0;
-        let mut step_3 = 0;
-        for a in self.adapters.iter() {
-            // println!("Stepped from {} to {}", jolts, a);
-            if (a - jolts) == 1 {
-                step_1 += 1;
+    pub fn with_rules(mut self, rules: JoltageRules) -> AdapterArray {
+        self.rules = rules;
+        return self;
+    }
+
+    // The device's joltage rating.
+    pub fn device(&self) -> i64 {
+        return self.adapters.last().cloned().unwrap_or(0) + self.rules.device_offset;
+    }
+
+    // Outlet, adapters and device, in joltage order.
+    fn chain(&self) -> Vec<i64> {
+        let mut chain = vec![0];
+        chain.extend(self.adapters.iter().cloned());
+        chain.push(self.device());
+        return chain;
+    }
+
+    // How many times each joltage difference occurs when every adapter is used,
+    // from the outlet up to the device.
+    pub fn differences(&self) -> BTreeMap<i64, usize> {
+        let mut histogram: BTreeMap<i64, usize> = BTreeMap::new();
+        for pair in self.chain().windows(2) {
+            *histogram.entry(pair[1] - pair[0]).or_insert(0) += 1;
+        }
+
+        return histogram;
+    }
+
+    // Check every adapter can be used, reporting the first gap the rules don't allow.
+    pub fn validate(&self) -> Result<(), ChainError> {
+        for pair in self.chain().windows(2) {
+            if !self.rules.allows(pair[1] - pair[0]) {
+                return Err(ChainError { from: pair[0], to: pair[1] });
             }
-            if (a-jolts) == 3 {
-                step_3 += 1;
+        }
+
+        return Ok(());
+    }
+
+    // Number of ways to connect the outlet to the device.
+    pub fn arrangements(&self) -> BigUint {
+        // For each step in the chain, compute how many ways there are to reach it
+        // from the ways to reach the joltages an allowed step below it.
+        let chain = self.chain();
+        let mut ways_to: Vec<BigUint> = vec![BigUint::from(0u32); chain.len()];
+        ways_to[0] = BigUint::from(1u32);  // Start with one way to get to 0 jolts.
+        for i in 1..chain.len() {
+            let mut ways = BigUint::from(0u32);
+            for j in (0..i).rev() {
+                let step = chain[i] - chain[j];
+                if step > self.rules.max_step() {
+                    break;
+                }
+                if self.rules.allows(step) {
+                    ways += &ways_to[j];
+                }
             }
-            jolts = *a;
+            ways_to[i] = ways;
         }
 
-        return Ok(step_1 * (step_3+1));  // step_3+1 because there's one more step up to device.
+        return ways_to[chain.len()-1].clone();
     }
 
-    fn part2(&self) -> Result<i64, &str> {
-        // For each step in the adapter chain, compute how many ways there are to reach it
-        // The number is N(j-3) + N(j-2) + N(j-1)
-        let mut ways_to: HashMap<i64, i64> = HashMap::new();
-        ways_to.insert(0, 1);  // Start with one way to get to 0 jolts.
-        let mut ways = 0;
-        for a in self.adapters.iter() {
-            ways = 0;
-            for diff in 1..4 {
-                if ways_to.contains_key(&(*a-diff)) {
-                    ways += ways_to.get(&(*a-diff)).unwrap();
+    // Every valid arrangement, as the adapters used.  Lazy, but there are a lot
+    // of them for anything but small inputs.
+    pub fn arrangements_iter(&self) -> Arrangements<'_> {
+        return Arrangements { array: self, chain: self.chain(), path: vec![0], next: vec![1] };
+    }
+}
+
+// Depth-first walk over the arrangements, trying smaller steps first.
+pub struct Arrangements<'a> {
+    array: &'a AdapterArray,
+    chain: Vec<i64>,   // outlet, adapters, device
+    path: Vec<usize>,  // chain indices used so far
+    next: Vec<usize>,  // for each entry in path, the next chain index to try after it
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        let rules = &self.array.rules;
+        while let Some(&current) = self.path.last() {
+            let depth = self.path.len() - 1;
+            if current == self.chain.len() - 1 {
+                // Reached the device.
+                let used: Vec<i64> = self.path[1..depth].iter().map(|i| self.chain[*i]).collect();
+                self.path.pop();
+                self.next.pop();
+                return Some(used);
+            }
+
+            let candidate = self.next[depth];
+            if candidate < self.chain.len() && self.chain[candidate] - self.chain[current] <= rules.max_step() {
+                self.next[depth] = candidate + 1;
+                if rules.allows(self.chain[candidate] - self.chain[current]) {
+                    self.path.push(candidate);
+                    self.next.push(candidate + 1);
                 }
             }
-            // println!("Ways to {}: {}", *a, ways);
-            ways_to.insert(*a, ways);
+            else {
+                // Nothing more reachable from here.
+                self.path.pop();
+                self.next.pop();
+            }
+        }
+
+        return None;
+    }
+}
+
+impl crate::Day for AdapterArray {
+    fn part1(&self) -> Result<i64, &str> {
+        if self.validate().is_err() {
+            return Err("Adapter chain has an impossible gap");
         }
+        let histogram = self.differences();
+        let count = |step: i64| *histogram.get(&step).unwrap_or(&0) as i64;
 
-        return Ok(ways);
+        return Ok(count(1) * count(3));
+    }
+
+    fn part2(&self) -> Result<i64, &str> {
+        return i64::try_from(&self.arrangements()).map_err(|_| "Too many arrangements for an i64");
     }
 }
 
@@ -83,6 +224,70 @@ mod tests {
         assert_eq!(ex2.adapters[30], 49);
     }
 
+    #[test]
+    fn test_rules() {
+        assert!(JoltageRules::new(&[], 3).is_err());
+        assert!(JoltageRules::new(&[0, 1], 3).is_err());
+        assert!(JoltageRules::new(&[1], 0).is_err());
+        assert_eq!(JoltageRules::new(&[3, 1, 2, 1], 3), Ok(JoltageRules::puzzle()));
+
+        // Without 2-jolt steps example 1 has fewer ways through.
+        let ex1 = AdapterArray::load("data/day10_example1.txt").with_rules(JoltageRules::new(&[1, 3], 3).unwrap());
+        assert_eq!(ex1.part2(), Ok(2));
+
+        // Device only accepts a 2-jolt step, which no adapter can reach it with.
+        let ex1 = AdapterArray::load("data/day10_example1.txt").with_rules(JoltageRules::new(&[1, 3], 2).unwrap());
+        assert_eq!(ex1.device(), 21);
+        assert_eq!(ex1.part2(), Ok(0));
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let short = AdapterArray { adapters: (1..=4).collect(), rules: JoltageRules::puzzle() };
+        assert_eq!(short.arrangements(), BigUint::from(7u32));
+
+        let long = AdapterArray { adapters: (1..=200).collect(), rules: JoltageRules::puzzle() };
+        assert!(long.arrangements() > BigUint::from(i64::MAX as u64));
+        assert_eq!(long.part2(), Err("Too many arrangements for an i64"));
+        assert_eq!(long.arrangements_iter().next(), Some((1..=200).collect()));
+    }
+
+    #[test]
+    fn test_arrangements_iter() {
+        let ex1 = &AdapterArray::load("data/day10_example1.txt");
+        let all: Vec<Vec<i64>> = ex1.arrangements_iter().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], ex1.adapters);
+        assert_eq!(all[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+
+        let ex2 = &AdapterArray::load("data/day10_example2.txt");
+        assert_eq!(ex2.arrangements_iter().count(), 19208);
+    }
+
+    #[test]
+    fn test_differences() {
+        let ex1 = &AdapterArray::load("data/day10_example1.txt");
+        let expected: BTreeMap<i64, usize> = vec![(1, 7), (3, 5)].into_iter().collect();
+        assert_eq!(ex1.differences(), expected);
+        assert_eq!(ex1.validate(), Ok(()));
+
+        let gap = &AdapterArray::load("data/day10_gap.txt");
+        let expected: BTreeMap<i64, usize> = vec![(1, 4), (2, 1), (3, 1), (4, 1)].into_iter().collect();
+        assert_eq!(gap.differences(), expected);
+        let err = gap.validate().unwrap_err();
+        assert_eq!(err, ChainError { from: 5, to: 9 });
+        assert_eq!(err.to_string(), "can't step from 5 to 9 jolts, a gap of 4");
+        assert_eq!(gap.part1(), Err("Adapter chain has an impossible gap"));
+        assert_eq!(gap.part2(), Ok(0));
+
+        // 2-jolt steps are fine unless the rules leave them out.
+        let gap = AdapterArray { adapters: vec![1, 2, 4, 5], rules: JoltageRules::puzzle() };
+        assert_eq!(gap.validate(), Ok(()));
+        assert_eq!(gap.part1(), Ok(3));
+        let gap = gap.with_rules(JoltageRules::new(&[1, 3], 3).unwrap());
+        assert_eq!(gap.validate(), Err(ChainError { from: 2, to: 4 }));
+    }
+
     #[test]
     fn test_part1() {
         let ex1 = &AdapterArray::load("data/day10_example1.txt");
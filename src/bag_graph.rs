@@ -0,0 +1,416 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+// Containment rules between bag colors, as a directed graph.
+// Edges run from the outer bag to the bags it directly contains.
+pub struct BagGraph {
+    names: Vec<String>,                 // node -> color
+    index: HashMap<String, usize>,      // color -> node
+    contains: Vec<Vec<(usize, i64)>>,   // node -> (inner node, count)
+    contained_in: Vec<Vec<usize>>,      // node -> outer nodes (reverse index)
+}
+
+impl BagGraph {
+    pub fn new() -> BagGraph {
+        BagGraph { names: Vec::new(), index: HashMap::new(), contains: Vec::new(), contained_in: Vec::new() }
+    }
+
+    // Node for a color, adding it if it's new.
+    pub fn add_bag(&mut self, color: &str) -> usize {
+        if let Some(node) = self.index.get(color) {
+            return *node;
+        }
+
+        let node = self.names.len();
+        self.names.push(String::from(color));
+        self.index.insert(String::from(color), node);
+        self.contains.push(Vec::new());
+        self.contained_in.push(Vec::new());
+
+        return node;
+    }
+
+    // Record that an <outer> bag holds <count> <inner> bags.
+    pub fn add_rule(&mut self, outer: &str, inner: &str, count: i64) {
+        let outer = self.add_bag(outer);
+        let inner = self.add_bag(inner);
+        self.contains[outer].push((inner, count));
+        self.contained_in[inner].push(outer);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.names.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.names.is_empty();
+    }
+
+    pub fn node(&self, color: &str) -> Option<usize> {
+        return self.index.get(color).cloned();
+    }
+
+    pub fn color(&self, node: usize) -> &str {
+        return &self.names[node];
+    }
+
+    // Bags directly inside a bag, with counts.
+    pub fn contents(&self, node: usize) -> &[(usize, i64)] {
+        return &self.contains[node];
+    }
+
+    // Colors of every bag that ultimately holds a <color> bag, sorted.
+    pub fn containers(&self, color: &str) -> Vec<&str> {
+        let start = match self.node(color) {
+            Some(node) => node,
+            None => return Vec::new(),
+        };
+
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut to_expand = vec![start];
+        while let Some(node) = to_expand.pop() {
+            for outer in &self.contained_in[node] {
+                if seen.insert(*outer) {
+                    to_expand.push(*outer);
+                }
+            }
+        }
+
+        let mut colors: Vec<&str> = seen.iter().map(|n| self.color(*n)).collect();
+        colors.sort();
+
+        return colors;
+    }
+
+    // Look for a chain of bags that contains itself, reachable from <start>
+    // (or anywhere, if start is None).  The chain starts and ends with the same color.
+    pub fn find_cycle_from(&self, start: Option<usize>) -> Option<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark { New, OnPath, Done }
+
+        let mut marks = vec![Mark::New; self.len()];
+        let roots: Vec<usize> = match start {
+            Some(node) => vec![node],
+            None => (0..self.len()).collect(),
+        };
+
+        for root in roots {
+            if marks[root] != Mark::New {
+                continue;
+            }
+
+            // Depth-first, keeping the current path and where we are in each node's contents.
+            let mut path: Vec<(usize, usize)> = vec![(root, 0)];
+            marks[root] = Mark::OnPath;
+            while let Some((node, next)) = path.last_mut() {
+                let node = *node;
+                if *next < self.contains[node].len() {
+                    let inner = self.contains[node][*next].0;
+                    *next += 1;
+                    match marks[inner] {
+                        Mark::New => {
+                            marks[inner] = Mark::OnPath;
+                            path.push((inner, 0));
+                        }
+                        Mark::OnPath => {
+                            // Found a loop: report from inner's place on the path back round to inner.
+                            let from = path.iter().position(|(n, _)| *n == inner).unwrap();
+                            let mut chain: Vec<String> = path[from..].iter().map(|(n, _)| self.names[*n].clone()).collect();
+                            chain.push(self.names[inner].clone());
+                            return Some(chain);
+                        }
+                        Mark::Done => {}
+                    }
+                }
+                else {
+                    marks[node] = Mark::Done;
+                    path.pop();
+                }
+            }
+        }
+
+        return None;
+    }
+
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        return self.find_cycle_from(None);
+    }
+
+    // Colors ordered so every bag comes before the bags it contains,
+    // or the offending chain if the rules are cyclic.
+    pub fn topological_order(&self) -> Result<Vec<&str>, Vec<String>> {
+        let mut outer_count: Vec<usize> = self.contained_in.iter().map(|v| v.len()).collect();
+        let mut ready: VecDeque<usize> = (0..self.len()).filter(|n| outer_count[*n] == 0).collect();
+        let mut order: Vec<&str> = Vec::new();
+
+        while let Some(node) = ready.pop_front() {
+            order.push(self.color(node));
+            for (inner, _) in &self.contains[node] {
+                outer_count[*inner] -= 1;
+                if outer_count[*inner] == 0 {
+                    ready.push_back(*inner);
+                }
+            }
+        }
+
+        if order.len() < self.len() {
+            return Err(self.find_cycle().unwrap());
+        }
+
+        return Ok(order);
+    }
+
+    // Total number of bags inside a <color> bag, or the chain that makes it infinite.
+    pub fn total_within(&self, color: &str) -> Result<i64, Vec<String>> {
+        let start = match self.node(color) {
+            Some(node) => node,
+            None => return Ok(0),
+        };
+        if let Some(chain) = self.find_cycle_from(Some(start)) {
+            return Err(chain);
+        }
+
+        let mut cache: HashMap<usize, i64> = HashMap::new();
+        return Ok(self.total_within_helper(start, &mut cache));
+    }
+
+    fn total_within_helper(&self, node: usize, cache: &mut HashMap<usize, i64>) -> i64 {
+        // If we've computed this before, take a shortcut.
+        if let Some(n) = cache.get(&node) {
+            return *n;
+        }
+
+        let mut n: i64 = 0;
+        for (inner, count) in &self.contains[node] {
+            n += count * (self.total_within_helper(*inner, cache) + 1);
+        }
+        cache.insert(node, n);
+
+        return n;
+    }
+
+    // Every chain of bags leading from an <outer> bag down to an <inner> bag,
+    // each listed outermost first.  Chains never visit a color twice.
+    pub fn chains(&self, outer: &str, inner: &str) -> Vec<Vec<String>> {
+        let mut chains: Vec<Vec<String>> = Vec::new();
+        if let (Some(from), Some(to)) = (self.node(outer), self.node(inner)) {
+            let mut path: Vec<usize> = vec![from];
+            self.chains_helper(to, &mut path, &mut chains);
+        }
+        chains.sort();
+
+        return chains;
+    }
+
+    fn chains_helper(&self, to: usize, path: &mut Vec<usize>, chains: &mut Vec<Vec<String>>) {
+        let node = *path.last().unwrap();
+        if node == to && path.len() > 1 {
+            chains.push(path.iter().map(|n| self.names[*n].clone()).collect());
+            return;
+        }
+
+        for (inner, _) in &self.contains[node] {
+            if *inner == to || !path.contains(inner) {
+                path.push(*inner);
+                self.chains_helper(to, path, chains);
+                path.pop();
+            }
+        }
+    }
+
+    // How many bags of each color end up inside a <color> bag, or the chain that makes it infinite.
+    pub fn bill_of_materials(&self, color: &str) -> Result<BTreeMap<String, i64>, Vec<String>> {
+        let start = match self.node(color) {
+            Some(node) => node,
+            None => return Ok(BTreeMap::new()),
+        };
+        if let Some(chain) = self.find_cycle_from(Some(start)) {
+            return Err(chain);
+        }
+
+        let mut cache: HashMap<usize, HashMap<usize, i64>> = HashMap::new();
+        let bom = self.bill_of_materials_helper(start, &mut cache);
+
+        return Ok(bom.iter().map(|(n, count)| (self.names[*n].clone(), *count)).collect());
+    }
+
+    fn bill_of_materials_helper(&self, node: usize, cache: &mut HashMap<usize, HashMap<usize, i64>>) -> HashMap<usize, i64> {
+        if let Some(bom) = cache.get(&node) {
+            return bom.clone();
+        }
+
+        let mut bom: HashMap<usize, i64> = HashMap::new();
+        for (inner, count) in &self.contains[node] {
+            *bom.entry(*inner).or_insert(0) += count;
+            for (n, inner_count) in self.bill_of_materials_helper(*inner, cache) {
+                *bom.entry(n).or_insert(0) += count * inner_count;
+            }
+        }
+        cache.insert(node, bom.clone());
+
+        return bom;
+    }
+
+    // The longest chain of bags nested inside a <color> bag, starting with color itself,
+    // or the chain that makes nesting endless.
+    pub fn deepest_path(&self, color: &str) -> Result<Vec<String>, Vec<String>> {
+        let start = match self.node(color) {
+            Some(node) => node,
+            None => return Ok(Vec::new()),
+        };
+        if let Some(chain) = self.find_cycle_from(Some(start)) {
+            return Err(chain);
+        }
+
+        // Depth below each node and which inner bag achieves it.
+        let mut cache: HashMap<usize, (usize, Option<usize>)> = HashMap::new();
+        self.depth_helper(start, &mut cache);
+
+        let mut path: Vec<String> = vec![self.names[start].clone()];
+        let mut node = start;
+        while let Some(inner) = cache[&node].1 {
+            path.push(self.names[inner].clone());
+            node = inner;
+        }
+
+        return Ok(path);
+    }
+
+    fn depth_helper(&self, node: usize, cache: &mut HashMap<usize, (usize, Option<usize>)>) -> usize {
+        if let Some((depth, _)) = cache.get(&node) {
+            return *depth;
+        }
+
+        let mut best: (usize, Option<usize>) = (0, None);
+        for (inner, _) in &self.contains[node] {
+            let depth = self.depth_helper(*inner, cache) + 1;
+            if depth > best.0 {
+                best = (depth, Some(*inner));
+            }
+        }
+        cache.insert(node, best);
+
+        return best.0;
+    }
+
+    // Graphviz description of the containment graph.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bags {\n");
+        for node in 0..self.len() {
+            if self.contains[node].is_empty() && self.contained_in[node].is_empty() {
+                dot += &format!("    \"{}\";\n", self.names[node]);
+            }
+            for (inner, count) in &self.contains[node] {
+                dot += &format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", self.names[node], self.names[*inner], count);
+            }
+        }
+        dot += "}\n";
+
+        return dot;
+    }
+}
+
+impl Default for BagGraph {
+    fn default() -> BagGraph {
+        return BagGraph::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> BagGraph {
+        let mut g = BagGraph::new();
+        g.add_rule("red", "white", 1);
+        g.add_rule("red", "yellow", 2);
+        g.add_rule("white", "gold", 1);
+        g.add_rule("yellow", "gold", 2);
+        g.add_rule("gold", "olive", 3);
+        g.add_bag("blue");
+        return g;
+    }
+
+    #[test]
+    fn test_containers() {
+        let g = example();
+        assert_eq!(g.len(), 6);
+        assert_eq!(g.containers("gold"), vec!["red", "white", "yellow"]);
+        assert_eq!(g.containers("red"), Vec::<&str>::new());
+        assert_eq!(g.containers("nope"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_order() {
+        let g = example();
+        let order = g.topological_order().unwrap();
+        let pos = |c: &str| order.iter().position(|o| *o == c).unwrap();
+        assert_eq!(order.len(), 6);
+        assert!(pos("red") < pos("white"));
+        assert!(pos("yellow") < pos("gold"));
+        assert!(pos("gold") < pos("olive"));
+        assert_eq!(g.total_within("gold"), Ok(3));
+        assert_eq!(g.total_within("red"), Ok((4 + 1) + 2 * (8 + 1)));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = example();
+        assert_eq!(g.find_cycle(), None);
+
+        g.add_rule("olive", "yellow", 1);
+        let chain = vec!["gold", "olive", "yellow", "gold"];
+        assert_eq!(g.find_cycle(), Some(chain.iter().map(|c| c.to_string()).collect()));
+        assert_eq!(g.topological_order().err().unwrap(), chain);
+        assert_eq!(g.total_within("white").err().unwrap(), chain);
+        assert_eq!(g.total_within("blue"), Ok(0));
+    }
+
+    #[test]
+    fn test_chains() {
+        let mut g = example();
+        assert_eq!(g.chains("red", "olive"), vec![
+            vec!["red", "white", "gold", "olive"],
+            vec!["red", "yellow", "gold", "olive"],
+        ]);
+        assert_eq!(g.chains("olive", "red"), Vec::<Vec<String>>::new());
+
+        // Cycles don't make chains endless.
+        g.add_rule("olive", "yellow", 1);
+        assert_eq!(g.chains("yellow", "olive"), vec![vec!["yellow", "gold", "olive"]]);
+        assert_eq!(g.chains("gold", "gold"), vec![vec!["gold", "olive", "yellow", "gold"]]);
+    }
+
+    #[test]
+    fn test_bill_of_materials() {
+        let g = example();
+        let bom = g.bill_of_materials("red").unwrap();
+        assert_eq!(bom.len(), 4);
+        assert_eq!(bom["white"], 1);
+        assert_eq!(bom["yellow"], 2);
+        assert_eq!(bom["gold"], 5);
+        assert_eq!(bom["olive"], 15);
+        assert_eq!(bom.values().sum::<i64>(), g.total_within("red").unwrap());
+        assert!(g.bill_of_materials("olive").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_deepest_path() {
+        let mut g = example();
+        g.add_rule("red", "olive", 1);
+        assert_eq!(g.deepest_path("red").unwrap(), vec!["red", "white", "gold", "olive"]);
+        assert_eq!(g.deepest_path("olive").unwrap(), vec!["olive"]);
+
+        g.add_rule("olive", "yellow", 1);
+        assert!(g.deepest_path("red").is_err());
+    }
+
+    #[test]
+    fn test_dot() {
+        let g = example();
+        let dot = g.to_dot();
+        assert!(dot.starts_with("digraph bags {\n"));
+        assert!(dot.contains("    \"red\" -> \"yellow\" [label=\"2\"];\n"));
+        assert!(dot.contains("    \"blue\";\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -1,64 +1,206 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-const SEATS: usize = 1024;
+// How the plane's seats are addressed: the first row_bits characters of a code
+// (F/B) pick the row, the remaining col_bits characters (L/R) pick the column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    row_bits: u32,
+    col_bits: u32,
+}
+
+impl Plane {
+    // The puzzle's plane: 128 rows of 8 seats.
+    pub const STANDARD: Plane = Plane { row_bits: 7, col_bits: 3 };
+
+    pub fn new(row_bits: u32, col_bits: u32) -> Result<Plane, String> {
+        match row_bits.checked_add(col_bits) {
+            Some(bits) if (1..=24).contains(&bits) => return Ok(Plane { row_bits: row_bits, col_bits: col_bits }),
+            _ => return Err(format!("a plane needs 1 to 24 bits of seat code, not {} + {}", row_bits, col_bits)),
+        }
+    }
+
+    pub fn row_bits(&self) -> u32 {
+        return self.row_bits;
+    }
+
+    pub fn col_bits(&self) -> u32 {
+        return self.col_bits;
+    }
+
+    pub fn rows(&self) -> u32 {
+        return 1 << self.row_bits;
+    }
+
+    pub fn cols(&self) -> u32 {
+        return 1 << self.col_bits;
+    }
+
+    pub fn seats(&self) -> usize {
+        return 1 << (self.row_bits + self.col_bits);
+    }
+
+    fn code_len(&self) -> usize {
+        return (self.row_bits + self.col_bits) as usize;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardingPass {
+    pub row: u32,
+    pub col: u32,
+    pub plane: Plane,
+}
+
+impl BoardingPass {
+    pub fn new(row: u32, col: u32, plane: Plane) -> Result<BoardingPass, String> {
+        if row >= plane.rows() || col >= plane.cols() {
+            return Err(format!("seat ({}, {}) is outside a plane of {} rows and {} columns",
+                               row, col, plane.rows(), plane.cols()));
+        }
+        return Ok(BoardingPass { row: row, col: col, plane: plane });
+    }
+
+    // Decode a code like "FBFBBFFRLR".
+    pub fn decode(code: &str, plane: Plane) -> Result<BoardingPass, String> {
+        let code = code.trim();
+        if code.chars().count() != plane.code_len() {
+            return Err(format!("'{}' should be {} characters long", code, plane.code_len()));
+        }
+
+        let mut row: u32 = 0;
+        let mut col: u32 = 0;
+        for (n, c) in code.chars().enumerate() {
+            let is_row = n < plane.row_bits as usize;
+            match (is_row, c) {
+                (true, 'F') => row <<= 1,
+                (true, 'B') => row = (row << 1) | 1,
+                (false, 'L') => col <<= 1,
+                (false, 'R') => col = (col << 1) | 1,
+                _ => {
+                    let expected = if is_row { "F or B" } else { "L or R" };
+                    return Err(format!("'{}' has '{}' at position {}, expected {}", code, c, n+1, expected));
+                }
+            }
+        }
+
+        return Ok(BoardingPass { row: row, col: col, plane: plane });
+    }
+
+    pub fn from_id(id: usize, plane: Plane) -> Result<BoardingPass, String> {
+        if id >= plane.seats() {
+            return Err(format!("seat ID {} is outside a plane of {} seats", id, plane.seats()));
+        }
+        return BoardingPass::new((id >> plane.col_bits) as u32, (id as u32) & (plane.cols() - 1), plane);
+    }
+
+    pub fn encode(&self) -> String {
+        let mut code = String::new();
+        for bit in (0..self.plane.row_bits).rev() {
+            code.push(if self.row & (1 << bit) != 0 { 'B' } else { 'F' });
+        }
+        for bit in (0..self.plane.col_bits).rev() {
+            code.push(if self.col & (1 << bit) != 0 { 'R' } else { 'L' });
+        }
+
+        return code;
+    }
+
+    pub fn id(&self) -> usize {
+        return ((self.row as usize) << self.plane.col_bits) | self.col as usize;
+    }
+}
 
 pub struct Boarding {
-    passes: [bool; SEATS],
+    plane: Plane,
+    passes: Vec<bool>,  // seat ID -> taken
 }
 
 impl Boarding {
-    fn new() -> Boarding {
-        let boarding = Boarding { passes: [false; SEATS] };
+    fn new(plane: Plane) -> Boarding {
+        let boarding = Boarding { plane: plane, passes: vec![false; plane.seats()] };
 
         return boarding;
     }
 
-    pub fn load(filename: &str) -> Boarding {
-        let mut boarding = Boarding::new();
+    pub fn load(filename: &str) -> Result<Boarding, String> {
+        return Boarding::load_plane(filename, Plane::STANDARD);
+    }
+
+    pub fn load_plane(filename: &str, plane: Plane) -> Result<Boarding, String> {
+        let mut boarding = Boarding::new(plane);
 
         let file = File::open(filename).unwrap();
         let reader = BufReader::new(file);
 
-        for line in reader.lines() {
-            let mut val: usize = 0;
-            for c in line.unwrap().chars() {
-                if (c == 'B') | (c == 'R') {
-                    val = (val << 1) | 1;
-                } else if (c == 'F') | (c == 'L') {
-                    val = val << 1;
-                }
+        for (n, line) in reader.lines().enumerate() {
+            let line = line.unwrap();
+            if line.trim().is_empty() {
+                continue;
             }
-            boarding.passes[val] = true;
+            let pass = BoardingPass::decode(&line, plane).map_err(|e| format!("line {}: {}", n+1, e))?;
+            boarding.passes[pass.id()] = true;
         }
 
-        return boarding;
+        return Ok(boarding);
     }
-}
 
-impl super::Day for Boarding {
-    fn part1(&self) -> Result<i64, &str> {
-        let mut max: usize = 0;
-
-        for n in 0..SEATS {
-            if self.passes[n] {
-                max = n;
+    // Every seat nobody has a pass for, in ID order.
+    pub fn empty_seats(&self) -> Vec<BoardingPass> {
+        let mut empty: Vec<BoardingPass> = Vec::new();
+        for (id, taken) in self.passes.iter().enumerate() {
+            if !taken {
+                empty.push(BoardingPass::from_id(id, self.plane).unwrap());
             }
         }
 
-        return Ok(max as i64);
+        return empty;
     }
 
-    fn part2(&self) -> Result<i64, &str> {
-        let mut found: usize = 0;
+    // Runs of empty seat IDs, as (first, last) inclusive.
+    pub fn gaps(&self) -> Vec<(usize, usize)> {
+        let mut gaps: Vec<(usize, usize)> = Vec::new();
+        let mut start: Option<usize> = None;
 
-        for n in 1..SEATS-1 {
-            if self.passes[n-1] & !self.passes[n] & self.passes[n+1] {
-                found = n;
+        for (id, taken) in self.passes.iter().enumerate() {
+            match (start, *taken) {
+                (None, false) => start = Some(id),
+                (Some(first), true) => {
+                    gaps.push((first, id - 1));
+                    start = None;
+                }
+                _ => {}
             }
         }
+        if let Some(first) = start {
+            gaps.push((first, self.passes.len() - 1));
+        }
+
+        return gaps;
+    }
 
-        return Ok(found as i64);
+    // Single empty seats with taken seats on both sides.
+    pub fn lone_empty_seats(&self) -> Vec<usize> {
+        return self.gaps().iter()
+            .filter(|(first, last)| first == last && *first > 0 && *last < self.passes.len() - 1)
+            .map(|(first, _)| *first)
+            .collect();
+    }
+}
+
+impl super::Day for Boarding {
+    fn part1(&self) -> Result<i64, &str> {
+        match self.passes.iter().rposition(|taken| *taken) {
+            Some(max) => return Ok(max as i64),
+            None => return Err("No boarding passes"),
+        }
+    }
+
+    fn part2(&self) -> Result<i64, &str> {
+        match self.lone_empty_seats().last() {
+            Some(found) => return Ok(*found as i64),
+            None => return Err("No empty seat between two taken seats"),
+        }
     }
 }
 
@@ -67,17 +209,73 @@ mod tests {
     use super::*;
     use crate::Day;
 
+    #[test]
+    fn test_codec() {
+        let pass = BoardingPass::decode("FBFBBFFRLR", Plane::STANDARD).unwrap();
+        assert_eq!((pass.row, pass.col, pass.id()), (44, 5, 357));
+        assert_eq!(pass.encode(), "FBFBBFFRLR");
+        assert_eq!(BoardingPass::from_id(357, Plane::STANDARD), Ok(pass));
+        assert_eq!(BoardingPass::new(44, 5, Plane::STANDARD), Ok(pass));
+
+        let examples = [("BFFFBBFRRR", 567), ("FFFBBBFRRR", 119), ("BBFFBBFRLL", 820)];
+        for (code, id) in examples.iter() {
+            let pass = BoardingPass::decode(code, Plane::STANDARD).unwrap();
+            assert_eq!(pass.id(), *id);
+            assert_eq!(BoardingPass::from_id(*id, Plane::STANDARD).unwrap().encode(), *code);
+        }
+    }
+
+    #[test]
+    fn test_codec_errors() {
+        assert_eq!(BoardingPass::decode("FBFBBFFRL", Plane::STANDARD).err(),
+                   Some(String::from("'FBFBBFFRL' should be 10 characters long")));
+        assert_eq!(BoardingPass::decode("FBFBBFRRLR", Plane::STANDARD).err(),
+                   Some(String::from("'FBFBBFRRLR' has 'R' at position 7, expected F or B")));
+        assert!(BoardingPass::decode("FBFBBFFRLX", Plane::STANDARD).is_err());
+        assert!(BoardingPass::from_id(1024, Plane::STANDARD).is_err());
+        assert!(BoardingPass::new(128, 0, Plane::STANDARD).is_err());
+        assert!(Plane::new(20, 10).is_err());
+        assert!(Plane::new(0, 0).is_err());
+        assert_eq!(Plane::new(u32::MAX, 1).err(),
+                   Some(String::from("a plane needs 1 to 24 bits of seat code, not 4294967295 + 1")));
+        assert_eq!(Plane::new(7, 3), Ok(Plane::STANDARD));
+        assert_eq!((Plane::STANDARD.row_bits(), Plane::STANDARD.col_bits()), (7, 3));
+    }
+
+    #[test]
+    fn test_small_plane() {
+        let plane = Plane::new(2, 2).unwrap();
+        let pass = BoardingPass::decode("BFRL", plane).unwrap();
+        assert_eq!((pass.row, pass.col, pass.id()), (2, 2, 10));
+
+        let boarding = Boarding::load_plane("data/day5_example2.txt", plane).unwrap();
+        assert_eq!(boarding.gaps(), vec![(0, 1), (4, 4), (7, 9), (13, 15)]);
+        assert_eq!(boarding.lone_empty_seats(), vec![4]);
+        assert_eq!(boarding.empty_seats().len(), 9);
+        assert_eq!(boarding.empty_seats()[0].encode(), "FFLL");
+        assert_eq!(boarding.part1(), Ok(12));
+        assert_eq!(boarding.part2(), Ok(4));
+    }
+
+    #[test]
+    fn test_example() {
+        let tt = &Boarding::load("data/day5_example1.txt").unwrap();
+
+        assert_eq!(tt.part1(), Ok(820));
+        assert!(Boarding::load_plane("data/day5_example1.txt", Plane::new(6, 3).unwrap()).is_err());
+    }
+
     #[test]
     fn test_part1() {
-        let tt = &Boarding::load("data/day5_input.txt");
+        let tt = &Boarding::load("data/day5_input.txt").unwrap();
 
         assert_eq!(tt.part1(), Result::Ok(901));
     }
 
     #[test]
     fn test_part2() {
-        let tt = &Boarding::load("data/day5_input.txt");
+        let tt = &Boarding::load("data/day5_input.txt").unwrap();
 
         assert_eq!(tt.part2(), Result::Ok(661));
     }
-}
\ No newline at end of file
+}
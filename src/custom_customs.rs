@@ -1,54 +1,115 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::collections::HashMap;
+use std::ops::{BitAnd, BitOr};
+
+// A set of questions (a-z) answered yes, one bit per question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Answers(u32);
+
+impl Answers {
+    pub const NONE: Answers = Answers(0);
+    pub const ALL: Answers = Answers((1 << 26) - 1);
+
+    // Characters other than a-z are ignored.
+    pub fn from_form(form: &str) -> Answers {
+        let mut bits: u32 = 0;
+        for c in form.chars() {
+            if c.is_ascii_lowercase() {
+                bits |= 1 << (c as u32 - 'a' as u32);
+            }
+        }
+
+        return Answers(bits);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.0.count_ones() as usize;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.0 == 0;
+    }
+
+    pub fn contains(&self, question: char) -> bool {
+        return question.is_ascii_lowercase() && self.0 & (1 << (question as u32 - 'a' as u32)) != 0;
+    }
+
+    // The questions in the set, in order.
+    pub fn questions(&self) -> Vec<char> {
+        return ('a'..='z').filter(|c| self.contains(*c)).collect();
+    }
+}
+
+impl BitOr for Answers {
+    type Output = Answers;
+
+    fn bitor(self, other: Answers) -> Answers {
+        return Answers(self.0 | other.0);
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Answers;
+
+    fn bitand(self, other: Answers) -> Answers {
+        return Answers(self.0 & other.0);
+    }
+}
 
 struct Group {
-    forms: Vec<String>,
-    anyone: HashMap<char, bool>,
-    everyone: HashMap<char, bool>,
+    forms: Vec<Answers>,
 }
 
 impl Group {
     fn new() -> Group {
-        return Group { forms: Vec::new(), anyone: HashMap::new(), everyone: HashMap::new() }
+        return Group { forms: Vec::new() }
     }
 
     fn add_form(&mut self, line: &str) {
-        if self.forms.len() == 0 {
-            // This is the first form registered for this group.  Set Anyone and Everyone
-            // groups to the letters in this line
-            for c in line.chars() {
-                self.anyone.insert(c, true);
-            }
-        }
-        else {
-            // Update anyone and everyone strings
-            // Add any missing chars to anyone
-            for c in line.chars() {
-                self.anyone.insert(c, true);
-            }
-        }
-        self.forms.push(line.to_string());
+        self.forms.push(Answers::from_form(line));
     }
 
     fn is_empty(&self) -> bool {
-        return self.forms.len() == 0;
+        return self.forms.is_empty();
+    }
+
+    // Questions anyone in the group answered yes to.
+    fn anyone(&self) -> Answers {
+        return self.forms.iter().fold(Answers::NONE, |acc, a| acc | *a);
+    }
+
+    // Questions everyone in the group answered yes to.
+    fn everyone(&self) -> Answers {
+        if self.forms.is_empty() {
+            return Answers::NONE;
+        }
+        return self.forms.iter().fold(Answers::ALL, |acc, a| acc & *a);
     }
 
-    fn set_everyone(&mut self) {
-        for c in self.anyone.keys() {
-            let mut everyone = true;
-            for form in self.forms.iter() {
-                // if c is not in this form, it wasn't common to everyone
-                if !form.contains(*c) {
-                    everyone = false;
+    // How many members answered yes to each question, a-z.
+    fn frequencies(&self) -> [usize; 26] {
+        let mut counts = [0; 26];
+        for form in &self.forms {
+            for (n, count) in counts.iter_mut().enumerate() {
+                if form.0 & (1 << n) != 0 {
+                    *count += 1;
                 }
             }
+        }
+
+        return counts;
+    }
 
-            if everyone {
-                self.everyone.insert(*c, true);
+    // Questions answered yes by at least k members.
+    fn at_least(&self, k: usize) -> Answers {
+        let mut bits: u32 = 0;
+        for (n, count) in self.frequencies().iter().enumerate() {
+            if *count >= k && *count > 0 {
+                bits |= 1 << n;
             }
         }
+
+        return Answers(bits);
     }
 }
 
@@ -79,9 +140,8 @@ impl Customs {
             let bare_line = line.trim();
 
             // if blank line...
-            if bare_line.len() <= 0 {
+            if bare_line.is_empty() {
                 // store previous group and start a new one
-                group.set_everyone();
                 customs.add_group(group);
 
                 group= Group::new();
@@ -93,12 +153,43 @@ impl Customs {
 
         // Add the last group if there's one in progress
         if !group.is_empty() {
-            group.set_everyone();
             customs.add_group(group);
         }
 
         return customs;
     }
+
+    // Sum over all groups of the number of questions at least k members answered yes to.
+    pub fn count_at_least(&self, k: usize) -> usize {
+        return self.groups.iter().map(|g| g.at_least(k).len()).sum();
+    }
+
+    // How many people answered yes to each question, a-z, over all groups.
+    pub fn frequencies(&self) -> [usize; 26] {
+        let mut counts = [0; 26];
+        for group in &self.groups {
+            for (total, count) in counts.iter_mut().zip(group.frequencies().iter()) {
+                *total += count;
+            }
+        }
+
+        return counts;
+    }
+
+    // How many groups had each question answered yes by anyone, a-z.
+    pub fn group_frequencies(&self) -> [usize; 26] {
+        let mut counts = [0; 26];
+        for group in &self.groups {
+            let anyone = group.anyone();
+            for (n, count) in counts.iter_mut().enumerate() {
+                if anyone.0 & (1 << n) != 0 {
+                    *count += 1;
+                }
+            }
+        }
+
+        return counts;
+    }
 }
 
 
@@ -108,17 +199,17 @@ impl super::Day for Customs {
         // Add up the number of questions answered yes by anyone over all the groups.
         let mut sum = 0;
         for group in self.groups.iter() {
-            sum += group.anyone.len();
+            sum += group.anyone().len();
         }
 
         return Ok(sum as i64);
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        // Add up the number of questions answered yes by anyone over all the groups.
+        // Add up the number of questions answered yes by everyone over all the groups.
         let mut sum = 0;
         for group in self.groups.iter() {
-            sum += group.everyone.len();
+            sum += group.everyone().len();
         }
 
         return Ok(sum as i64);
@@ -130,6 +221,45 @@ mod tests {
     use super::*;
     use crate::Day;
 
+    #[test]
+    fn test_answers() {
+        let a = Answers::from_form("abc");
+        let b = Answers::from_form("bcd");
+        assert_eq!((a | b).questions(), vec!['a', 'b', 'c', 'd']);
+        assert_eq!((a & b).questions(), vec!['b', 'c']);
+        assert_eq!(a.len(), 3);
+        assert!(a.contains('a'));
+        assert!(!a.contains('d'));
+        assert!((a & Answers::from_form("xyz")).is_empty());
+    }
+
+    #[test]
+    fn test_example() {
+        let tt = &Customs::load("data/day6_example1.txt");
+
+        assert_eq!(tt.part1(), Ok(11));
+        assert_eq!(tt.part2(), Ok(6));
+    }
+
+    #[test]
+    fn test_queries() {
+        let tt = &Customs::load("data/day6_example1.txt");
+
+        assert_eq!(tt.count_at_least(1), 11);
+        assert_eq!(tt.count_at_least(2), 2);
+        assert_eq!(tt.count_at_least(4), 1);
+
+        let freq = tt.frequencies();
+        assert_eq!(freq[0], 8);  // a
+        assert_eq!(freq[1], 4);  // b
+        assert_eq!(freq[2], 3);  // c
+        assert_eq!(freq.iter().sum::<usize>(), 15);
+
+        let groups = tt.group_frequencies();
+        assert_eq!(groups[0], 4);
+        assert_eq!(groups[1], 4);
+    }
+
     #[test]
     fn test_part1() {
         let tt = &Customs::load("data/day6_input.txt");
@@ -143,4 +273,4 @@ mod tests {
 
         assert_eq!(tt.part2(), Result::Ok(3039));
     }
-}
\ No newline at end of file
+}
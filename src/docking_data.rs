@@ -1,175 +1,231 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
+use std::fmt;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
 
+// Width of the decoder chip's memory words, in bits.
+pub const WORD_WIDTH: usize = 36;
+
 pub struct DockingData {
+    width: usize,
     instructions: Vec<Instruction>,
 }
 
+// A mask, pre-compiled into words so it can be applied without re-scanning the string.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mask {
+    and: u64,       // bits to keep from the original word (X and 1 positions)
+    or: u64,        // bits forced to 1
+    floating: u64,  // X positions
+}
+
 enum Instruction {
-    Mask(String),
+    Mask(Mask),
     Write { addr: u64, value: u64 },
 }
 
-impl DockingData {
-    pub fn load(filename: &str) -> DockingData {
-        lazy_static! {
-            static ref MASK_RE: Regex = Regex::new("mask = ([01X]+)").unwrap();
-            static ref WRITE_RE: Regex = Regex::new("mem\\[([0-9]+)\\] = ([0-9]+)").unwrap();
-        }
-        let mut instructions: Vec<Instruction> = Vec::new();
+// Error produced when an input line can't be loaded.
+#[derive(Debug, PartialEq)]
+pub struct LoadError {
+    pub line: usize,     // 1-based line number, 0 if the problem isn't with a line
+    pub text: String,    // offending line
+    pub reason: String,
+}
 
-        let file = File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+// How to treat lines that aren't recognised as instructions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseMode {
+    Strict,   // report the first unrecognised line as an error
+    Lenient,  // skip unrecognised lines
+}
 
-        for line in reader.lines() {
-            let l = &line.unwrap();
-            match MASK_RE.captures(l) {
-                Some(cap) => {
-                    // We have a match for a mask instruction
-                    instructions.push( Instruction::Mask(cap[1].to_string()));
-                }
-                _ => {}
-            }
-            match WRITE_RE.captures(l) {
-                Some(cap) => {
-                    // We have a match for a write instruction
-                    instructions.push( Instruction::Write { addr: cap[1].parse().unwrap(),
-                                                                  value: cap[2].parse().unwrap(),
-                    });
-                }
-                _ => {}
-            }
+// Which version of the decoder chip to emulate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decoder {
+    V1,  // mask applies to values
+    V2,  // mask applies to addresses, with floating bits
+}
 
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.reason);
         }
+        write!(f, "line {}: {} ({})", self.line, self.reason, self.text)
+    }
+}
+
+// Every bit of a word of the given width.
+fn word_bits(width: usize) -> u64 {
+    return if width >= 64 { !0 } else { (1u64 << width) - 1 };
+}
 
-        return DockingData {instructions: instructions };
+impl Mask {
+    // A mask that leaves every bit alone.
+    fn transparent(width: usize) -> Mask {
+        return Mask { and: word_bits(width), or: 0, floating: word_bits(width) };
     }
 
-    fn mask_value(mask: &str, value: u64) -> u64 {
-        let mut new_value: u64 = value;
-        let mut bit: u64 = 1 << 35;
+    fn parse(s: &str, width: usize) -> Result<Mask, String> {
+        if s.len() != width {
+            return Err(format!("mask has {} bits, expected {}", s.len(), width));
+        }
+
+        let mut mask = Mask { and: word_bits(width), or: 0, floating: 0 };
+        let mut bit: u64 = 1 << (width - 1);
 
-        for c in mask.chars() {
+        for c in s.chars() {
             match c {
                 '0' => {
-                    new_value &= !bit;
+                    mask.and &= !bit;
                 }
                 '1' => {
-                    new_value |= bit;
+                    mask.or |= bit;
                 }
                 'X' => {
-                    // no change.
+                    mask.floating |= bit;
                 }
                 _ => {
-                    panic!("Invalid mask character.");
+                    return Err(format!("invalid mask character '{}'", c));
                 }
             }
             bit >>= 1;
         }
 
-        return new_value;
+        return Ok(mask);
     }
 
-    fn mask_addr(mask: &str, addr: u64) -> Vec<u64> {
-        let mut addrs: Vec<u64> = Vec::new();
+    // Version 1 decoder: overwrite value bits with the mask's 0s and 1s.
+    fn apply_value(&self, value: u64) -> u64 {
+        return (value & self.and) | self.or;
+    }
 
-        // initially populate addrs with just the unmodified address
-        addrs.push(addr);
+    // Version 2 decoder: force 1s, then expand every combination of floating bits.
+    fn apply_addr(&self, addr: u64) -> Vec<u64> {
+        let base = (addr | self.or) & !self.floating;
+        let mut addrs: Vec<u64> = Vec::with_capacity(1 << self.floating.count_ones());
 
-        // Now go through the mask and use it to modify and extend the set of addrs.
-        for n in 0..36 {
-            let bit = 1 << (35-n);
-            match &mask[n..n+1] {
-                "0" => {
-                    // No change
-                }
-                "1" => {
-                    // Overwrite this bit with 1 in all versions of the address.
-                    for a in addrs.iter_mut() {
-                        *a |= bit;
-                    }
-                }
-                "X" => {
-                    // For each entry in addrs, add another entry where this bit is the opposite.
-                    // (So there will be two entries, with the bit having both 0 and 1)
-                    let mut alt_addrs: Vec<u64> = Vec::new();
-                    for a in addrs.iter() {
-                        alt_addrs.push(*a ^ bit);
-                    }
-                    addrs.append(&mut alt_addrs);
-                }
-                _ => {
-                    panic!("Invalid component of mask.");
-                }
+        // Walk every subset of the floating bits.
+        let mut sub: u64 = self.floating;
+        loop {
+            addrs.push(base | sub);
+            if sub == 0 {
+                break;
             }
+            sub = (sub - 1) & self.floating;
         }
 
         return addrs;
     }
+}
 
-    fn run_part1(&self) -> u64 {
-        let mut mem: HashMap<u64, u64> = HashMap::new();  // addr -> value
-        let mut mask: &str = "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX";
+impl DockingData {
+    pub fn load(filename: &str) -> Result<DockingData, LoadError> {
+        return DockingData::load_with(filename, WORD_WIDTH, ParseMode::Strict);
+    }
 
-        for i in &self.instructions {
-            match i {
-                Instruction::Mask(_mask) => {
-                    mask = &_mask;
-                }
-                Instruction::Write { addr: _addr, value: _value } => {
-                    mem.insert(*_addr, DockingData::mask_value(mask, *_value));
-                }
-            }
+    pub fn load_with(filename: &str, width: usize, mode: ParseMode) -> Result<DockingData, LoadError> {
+        lazy_static! {
+            static ref MASK_RE: Regex = Regex::new("^mask = (.*)$").unwrap();
+            static ref WRITE_RE: Regex = Regex::new("^mem\\[([0-9]+)\\] = ([0-9]+)$").unwrap();
+        }
+        if width == 0 || width > 64 {
+            return Err(LoadError { line: 0, text: String::new(), reason: format!("word width must be 1..=64 bits, not {}", width) });
         }
 
-        // Sum contents of memory
-        let mut sum: u64 = 0;
-        for a in mem.keys() {
-            sum += *mem.get(a).unwrap() as u64;
+        let mut instructions: Vec<Instruction> = Vec::new();
+
+        let file = File::open(filename).unwrap();
+        let reader = BufReader::new(file);
+
+        for (n, line) in reader.lines().enumerate() {
+            let l = &line.unwrap();
+            let error = |reason: String| LoadError { line: n+1, text: l.to_string(), reason: reason };
+
+            if let Some(cap) = MASK_RE.captures(l.trim()) {
+                // We have a match for a mask instruction
+                let mask = Mask::parse(cap[1].trim(), width).map_err(error)?;
+                instructions.push(Instruction::Mask(mask));
+            }
+            else if let Some(cap) = WRITE_RE.captures(l.trim()) {
+                // We have a match for a write instruction
+                match (cap[1].parse(), cap[2].parse()) {
+                    (Ok(addr), Ok(value)) => {
+                        if addr & !word_bits(width) != 0 {
+                            return Err(error(format!("address {} doesn't fit in {} bits", addr, width)));
+                        }
+                        if value & !word_bits(width) != 0 {
+                            return Err(error(format!("value {} doesn't fit in {} bits", value, width)));
+                        }
+                        instructions.push(Instruction::Write { addr: addr, value: value });
+                    }
+                    _ => {
+                        return Err(error(String::from("number out of range")));
+                    }
+                }
+            }
+            else if l.trim().is_empty() {
+                // Blank lines are harmless in either mode.
+            }
+            else if mode == ParseMode::Strict {
+                return Err(error(String::from("unrecognised instruction")));
+            }
         }
 
-        return sum;
+        return Ok(DockingData { width: width, instructions: instructions });
     }
 
-    fn run_part2(&self) -> u64 {
+    // Run the program through the given decoder, returning final memory contents.
+    fn run(&self, decoder: Decoder) -> HashMap<u64, u64> {
         let mut mem: HashMap<u64, u64> = HashMap::new();  // addr -> value
-        let mut mask: &str = "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX";
+        let mut mask = Mask::transparent(self.width);
 
         for i in &self.instructions {
             match i {
                 Instruction::Mask(_mask) => {
-                    mask = &_mask;
+                    mask = *_mask;
                 }
                 Instruction::Write { addr: _addr, value: _value } => {
-                    let addrs = DockingData::mask_addr(mask, *_addr);
-                    for a in addrs.iter() {
-                        mem.insert(*a, *_value);
+                    match decoder {
+                        Decoder::V1 => {
+                            mem.insert(*_addr, mask.apply_value(*_value));
+                        }
+                        Decoder::V2 => {
+                            for a in mask.apply_addr(*_addr) {
+                                mem.insert(a, *_value);
+                            }
+                        }
                     }
                 }
             }
         }
 
-        // Sum contents of memory
-        let mut sum: u64 = 0;
-        for a in mem.keys() {
-            sum += *mem.get(a).unwrap() as u64;
-        }
+        return mem;
+    }
+
+    // Final (address, value) pairs after running the program, sorted by address.
+    pub fn memory(&self, decoder: Decoder) -> Vec<(u64, u64)> {
+        let mut dump: Vec<(u64, u64)> = self.run(decoder).into_iter().collect();
+        dump.sort();
+
+        return dump;
+    }
 
-        return sum;
+    fn mem_sum(&self, decoder: Decoder) -> u64 {
+        return self.run(decoder).values().sum();
     }
 }
 
 impl super::Day for DockingData {
     fn part1(&self) -> Result<i64, &str> {
-        return Ok(self.run_part1() as i64);
+        return Ok(self.mem_sum(Decoder::V1) as i64);
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        return Ok(self.run_part2() as i64);
+        return Ok(self.mem_sum(Decoder::V2) as i64);
     }
 }
 
@@ -180,37 +236,96 @@ mod tests {
 
     #[test]
     fn test_load() {
-        let dd = &DockingData::load("data/day14_example1.txt");
+        let dd = &DockingData::load("data/day14_example1.txt").unwrap();
         assert_eq!(dd.instructions.len(), 4);
     }
 
+    #[test]
+    fn test_load_bad_mask() {
+        let err = DockingData::load("data/day14_bad_mask.txt").err().unwrap();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.text, "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXXZX");
+        assert_eq!(err.reason, "invalid mask character 'Z'");
+    }
+
+    #[test]
+    fn test_load_wrong_width() {
+        let err = DockingData::load_with("data/day14_example1.txt", 8, ParseMode::Strict).err().unwrap();
+        assert_eq!(err.line, 1);
+        assert_eq!(err.reason, "mask has 36 bits, expected 8");
+
+        let err = DockingData::load_with("data/day14_wide_value.txt", 4, ParseMode::Strict).err().unwrap();
+        assert_eq!(err.to_string(), "line 2: value 300 doesn't fit in 4 bits (mem[1] = 300)");
+        let err = DockingData::load_with("data/day14_wide_addr.txt", 4, ParseMode::Strict).err().unwrap();
+        assert_eq!(err.to_string(), "line 2: address 100 doesn't fit in 4 bits (mem[100] = 3)");
+
+        for width in [0, 65].iter() {
+            let err = DockingData::load_with("data/day14_example1.txt", *width, ParseMode::Strict).err().unwrap();
+            assert_eq!(err.line, 0);
+            assert_eq!(err.to_string(), format!("word width must be 1..=64 bits, not {}", width));
+        }
+    }
+
+    #[test]
+    fn test_load_unrecognised() {
+        let err = DockingData::load("data/day14_bad_line.txt").err().unwrap();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.text, "mem[7 = 101");
+        assert_eq!(err.reason, "unrecognised instruction");
+
+        let dd = DockingData::load_with("data/day14_bad_line.txt", WORD_WIDTH, ParseMode::Lenient).unwrap();
+        assert_eq!(dd.instructions.len(), 3);
+    }
+
+    #[test]
+    fn test_memory() {
+        let dd = &DockingData::load("data/day14_example1.txt").unwrap();
+        assert_eq!(dd.memory(Decoder::V1), vec![(7, 101), (8, 64)]);
+
+        let dd = &DockingData::load("data/day14_example2.txt").unwrap();
+        let dump = dd.memory(Decoder::V2);
+        assert_eq!(dump.len(), 10);
+        assert_eq!(dump[0], (16, 1));
+        assert_eq!(dump[9], (59, 100));
+    }
+
+    #[test]
+    fn test_mask() {
+        let mask = Mask::parse("X1X0", 4).unwrap();
+        assert_eq!(mask.apply_value(0b0000), 0b0100);
+        assert_eq!(mask.apply_value(0b1111), 0b1110);
+        let mut addrs = mask.apply_addr(0b0001);
+        addrs.sort();
+        assert_eq!(addrs, vec![0b0101, 0b0111, 0b1101, 0b1111]);
+    }
+
     #[test]
     fn test_part1_ex1() {
-        let dd = &DockingData::load("data/day14_example1.txt");
+        let dd = &DockingData::load("data/day14_example1.txt").unwrap();
         assert_eq!(dd.part1(), Ok(165));
     }
 
     #[test]
     fn test_part1_ex2() {
-        let dd = &DockingData::load("data/day14_example2.txt");
+        let dd = &DockingData::load("data/day14_example2.txt").unwrap();
         assert_eq!(dd.part1(), Ok(51));
     }
 
     #[test]
     fn test_part1() {
-        let dd = &DockingData::load("data/day14_input.txt");
+        let dd = &DockingData::load("data/day14_input.txt").unwrap();
         assert_eq!(dd.part1(), Ok(10050490168421));
     }
 
     #[test]
     fn test_part2_ex2() {
-        let dd = &DockingData::load("data/day14_example2.txt");
+        let dd = &DockingData::load("data/day14_example2.txt").unwrap();
         assert_eq!(dd.part2(), Ok(208));
     }
 
     #[test]
     fn test_part2() {
-        let dd = &DockingData::load("data/day14_input.txt");
+        let dd = &DockingData::load("data/day14_input.txt").unwrap();
         assert_eq!(dd.part2(), Ok(2173858456958));
     }
 }
@@ -1,10 +1,88 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::collections::{HashMap, VecDeque};
 
 // Advent of Code 2020, Day 9.
 
 pub const PREAMBLE_LEN: usize = 25;
 
+// Checks a stream of XMAS numbers: after the preamble, each number must be the
+// sum of two numbers at different positions among the previous preamble_len.
+// The window is kept as a multiset so each check is one pass over the window.
+pub struct Validator {
+    preamble_len: usize,
+    window: VecDeque<i64>,
+    counts: HashMap<i64, usize>,  // value -> occurrences in window
+}
+
+impl Validator {
+    pub fn new(preamble_len: usize) -> Validator {
+        Validator { preamble_len: preamble_len, window: VecDeque::new(), counts: HashMap::new() }
+    }
+
+    // Whether value is the sum of two numbers in the window.
+    fn is_sum(&self, value: i64) -> bool {
+        for x in &self.window {
+            let y = value - *x;
+            let needed = if y == *x { 2 } else { 1 };
+            if self.counts.get(&y).cloned().unwrap_or(0) >= needed {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    // Take the next number.  None while still reading the preamble, otherwise whether it's valid.
+    pub fn push(&mut self, value: i64) -> Option<bool> {
+        let result = if self.window.len() < self.preamble_len {
+            None
+        }
+        else {
+            Some(self.is_sum(value))
+        };
+
+        self.window.push_back(value);
+        *self.counts.entry(value).or_insert(0) += 1;
+        if self.window.len() > self.preamble_len {
+            let old = self.window.pop_front().unwrap();
+            let count = self.counts.get_mut(&old).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&old);
+            }
+        }
+
+        return result;
+    }
+}
+
+// Iterator over the invalid numbers in a stream, as (index, value).
+pub struct InvalidValues<I: Iterator<Item = i64>> {
+    numbers: I,
+    index: usize,
+    validator: Validator,
+}
+
+impl<I: Iterator<Item = i64>> Iterator for InvalidValues<I> {
+    type Item = (usize, i64);
+
+    fn next(&mut self) -> Option<(usize, i64)> {
+        for value in &mut self.numbers {
+            let index = self.index;
+            self.index += 1;
+            if self.validator.push(value) == Some(false) {
+                return Some((index, value));
+            }
+        }
+        return None;
+    }
+}
+
+// Every invalid number in a stream, with its index.
+pub fn invalid_values<I: IntoIterator<Item = i64>>(numbers: I, preamble_len: usize) -> InvalidValues<I::IntoIter> {
+    return InvalidValues { numbers: numbers.into_iter(), index: 0, validator: Validator::new(preamble_len) };
+}
+
 pub struct EncodingError {
     values: Vec<i64>,
     preamble_len: usize,
@@ -24,73 +102,72 @@ impl EncodingError {
         return EncodingError { values: vals, preamble_len: preamble_len }
     }
 
+    // Every value that doesn't fit the rule, as (index, value).
+    pub fn all_invalid(&self) -> Vec<(usize, i64)> {
+        return invalid_values(self.values.iter().cloned(), self.preamble_len).collect();
+    }
+
     fn first_invalid(&self) -> Result<i64, &str> {
         // Find first value that doesn't fit the rule.
+        match invalid_values(self.values.iter().cloned(), self.preamble_len).next() {
+            Some((_, value)) => return Ok(value),
+            None => return Err("No invalid value found."),
+        }
+    }
 
-        for n in self.preamble_len..self.values.len() {
-            // value n must be the sum of two distinct values from n-preamble_len to n-1
-            let mut is_valid = false;
-            for index1 in n-self.preamble_len .. n-1 {
-                for index2 in index1+1..n {
-                    let sum = self.values.get(index1).unwrap() + self.values.get(index2).unwrap();
-                    if sum == *self.values.get(n).unwrap() {
-                        is_valid = true;
-                    }
-                }
-            }
+    // Every contiguous range of at least two values summing to target, as
+    // (first, last) indices, inclusive.  Works from prefix sums, so it doesn't
+    // depend on the values being positive.
+    pub fn ranges_summing_to(&self, target: i64) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        // prefix sum -> indices i where sum(values[..i]) is that sum
+        let mut starts: HashMap<i64, Vec<usize>> = HashMap::new();
+        let mut prefix: Vec<i64> = vec![0];
+        for v in &self.values {
+            prefix.push(prefix[prefix.len()-1] + v);
+        }
 
-            if !is_valid {
-                // print!("Found invalid value {val} at index {n}\n", val=self.values.get(n).unwrap(), n=n);
-                return Ok(*self.values.get(n).unwrap());
+        for end in 2..prefix.len() {
+            // Only make start end-2 available now, so every range holds two or more values.
+            starts.entry(prefix[end-2]).or_default().push(end-2);
+            if let Some(found) = starts.get(&(prefix[end] - target)) {
+                for start in found {
+                    ranges.push((*start, end-1));
+                }
             }
         }
-        return Err("No invalid value found.");
+        ranges.sort();
+
+        return ranges;
     }
 
     fn weakness(&self, value: i64) -> Result<i64, &str> {
-        // Search for first contiguous range that sums to the given value
-        let mut lower: usize = 0;
-        let mut upper: usize = 0;
-        let mut sum: i64 = 0;
-        let end = self.values.len();
-
-        while (upper <= end) & (sum != value) {
-            if sum < value {
-                // Grow the contiguous range from the upper end
-                sum += *self.values.get(upper).unwrap();
-                upper += 1;
+        // Use the first contiguous range that sums to the given value
+        match self.ranges_summing_to(value).first() {
+            Some((first, last)) => {
+                let minimum = self.values[*first..=*last].iter().min().unwrap();
+                let maximum = self.values[*first..=*last].iter().max().unwrap();
+
+                // Add min and max to produce the "weakness"
+                return Ok(minimum+maximum);
             }
-            else {
-                // Shrink contiguous range from the lower end
-                sum -= *self.values.get(lower).unwrap();
-                lower += 1;
+            None => {
+                // Didn't find a range that fit the value we were looking for.
+                return Err("Matching range not found.");
             }
         }
-
-        if sum == value {
-            // Found the contiguous range that fits, find min and max in that range
-            let minimum = self.values[lower..upper].iter().min().unwrap();
-            let maximum = self.values[lower..upper].iter().max().unwrap();
-
-            // Add min and max to produce the "weakness"
-            return Ok(minimum+maximum);
-        }
-        else {
-            // Didn't find a range that fit the value we were looking for.
-            return Err("Matching range not found.");
-        }
     }
 }
 
 impl super::Day for EncodingError  {
     fn part1(&self) -> Result<i64, &str> {
-        return Ok(self.first_invalid().unwrap());
+        return self.first_invalid();
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        let invalid = self.first_invalid().unwrap();
+        let invalid = self.first_invalid()?;
 
-        return Ok(self.weakness(invalid).unwrap());
+        return self.weakness(invalid);
     }
 }
 
@@ -112,12 +189,53 @@ mod tests {
         assert_eq!(ee.first_invalid(), Ok(127));
     }
 
+    #[test]
+    fn test_validator() {
+        let mut v = Validator::new(2);
+        assert_eq!(v.push(1), None);
+        assert_eq!(v.push(2), None);
+        assert_eq!(v.push(3), Some(true));
+        assert_eq!(v.push(4), Some(false));   // window is 2, 3
+        assert_eq!(v.push(7), Some(true));    // window is 3, 4
+        assert_eq!(v.push(14), Some(false));  // 7 + 7 needs two 7s
+    }
+
+    #[test]
+    fn test_all_invalid() {
+        let ee = &EncodingError::load("data/day9_example1.txt", 5);
+        assert_eq!(ee.all_invalid()[0], (14, 127));
+
+        let numbers = vec![1, 2, 3, 4, 7, 14, 21, 100, 35];
+        let invalid: Vec<(usize, i64)> = invalid_values(numbers, 2).collect();
+        assert_eq!(invalid, vec![(3, 4), (5, 14), (7, 100), (8, 35)]);
+    }
+
     #[test]
     fn test_weakness() {
         let ee = &EncodingError::load("data/day9_example1.txt", 5);
         assert_eq!(ee.weakness(127), Ok(62));
     }
 
+    #[test]
+    fn test_ranges() {
+        let ee = &EncodingError::load("data/day9_example1.txt", 5);
+        assert_eq!(ee.ranges_summing_to(127), vec![(2, 5)]);
+        assert_eq!(ee.ranges_summing_to(35), vec![(1, 2)]);
+
+        let ee = &EncodingError { values: vec![3, -1, 4, -3, 2, 5], preamble_len: 2 };
+        assert_eq!(ee.ranges_summing_to(3), vec![(0, 3), (1, 2), (2, 4)]);
+        assert_eq!(ee.ranges_summing_to(0), vec![(1, 3)]);
+        assert_eq!(ee.weakness(3), Ok(1));
+        assert_eq!(ee.weakness(100), Err("Matching range not found."));
+    }
+
+    #[test]
+    fn test_long_preamble() {
+        let ee = &EncodingError::load("data/day9_example1.txt", 900);
+        assert_eq!(ee.part1(), Err("No invalid value found."));
+        assert_eq!(ee.part2(), Err("No invalid value found."));
+    }
+
     #[test]
     fn test_part1() {
         let ee = &EncodingError::load("data/day9_input.txt", PREAMBLE_LEN);
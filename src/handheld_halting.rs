@@ -3,12 +3,13 @@ use std::io::{BufRead, BufReader};
 use std::collections::HashMap;
 use regex::Regex;
 
-struct Instruction {
-    opcode: String,
-    operand: i32,
+pub struct Instruction {
+    pub opcode: String,
+    pub operand: i32,
 }
 
-struct Processor {
+// Runs a program, optionally with one instruction's opcode patched.
+pub struct Processor {
     patch_addr: i32,
     patch_opcode: String,
     pc: i32,
@@ -17,7 +18,7 @@ struct Processor {
 }
 
 impl Processor {
-    fn new() -> Processor {
+    pub fn new() -> Processor {
         Processor {
             pc: 0,
             acc: 0,
@@ -26,7 +27,7 @@ impl Processor {
             executed: HashMap::new() }
     }
 
-    fn reset(&mut self) {
+    pub fn reset(&mut self) {
         self.pc = 0;
         self.acc = 0;
         self.patch_addr = -1;
@@ -34,11 +35,16 @@ impl Processor {
         self.executed.clear();
     }
 
-    fn patch(&mut self, addr: i32, opcode: &str) {
+    pub fn patch(&mut self, addr: i32, opcode: &str) {
         self.patch_addr = addr;
         self.patch_opcode = String::from(opcode);
     }
 
+    // Accumulator, as it was when the last run stopped.
+    pub fn acc(&self) -> i32 {
+        return self.acc;
+    }
+
     fn execute(&mut self, instr: &Instruction) {
         // set this pc in executed hash.
         self.executed.insert(self.pc, true);
@@ -64,7 +70,8 @@ impl Processor {
         self.pc = next_pc;
     }
 
-    fn run(&mut self, program: &HashMap<i32, Instruction>) -> Result<i32, &str> {
+    // Run from address 0.  Ok(acc) if the program ends, Err if an instruction would run twice.
+    pub fn run(&mut self, program: &HashMap<i32, Instruction>) -> Result<i32, &str> {
         self.pc = 0;
         self.acc = 0;
         self.executed.clear();
@@ -100,16 +107,22 @@ impl Processor {
     }
 }
 
+impl Default for Processor {
+    fn default() -> Processor {
+        return Processor::new();
+    }
+}
+
 pub struct Halting {
     program: HashMap<i32, Instruction>,  // addr -> instruction
 }
 
 impl Halting {
-    fn new() -> Halting {
+    pub fn new() -> Halting {
         Halting {program: HashMap::new()}
     }
 
-    fn store(&mut self, addr:i32, opcode: &str, operand: i32) {
+    pub fn store(&mut self, addr:i32, opcode: &str, operand: i32) {
         self.program.insert(addr, Instruction {opcode: String::from(opcode), operand: operand} );
     }
 
@@ -142,9 +155,20 @@ impl Halting {
 
         return halting;
     }
+
+    // Program as addr -> instruction, for running with a Processor.
+    pub fn program(&self) -> &HashMap<i32, Instruction> {
+        return &self.program;
+    }
 }
 
 
+impl Default for Halting {
+    fn default() -> Halting {
+        return Halting::new();
+    }
+}
+
 impl super::Day for Halting {
     fn part1(&self) -> Result<i64, &str> {
         // Get accumulator just before an instruction runs a second time
@@ -152,7 +176,7 @@ impl super::Day for Halting {
         let mut p = Processor::new();
         let _ = p.run(&self.program);
 
-        return Ok(p.acc as i64);
+        return Ok(p.acc() as i64);
     }
 
     fn part2(&self) -> Result<i64, &str> {
@@ -1,25 +1,25 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::collections::HashMap;
+use crate::bag_graph::BagGraph;
 use lazy_static::lazy_static;
 use regex::Regex;
 
 pub struct Haversacks {
     // Represents rules like this:
     // "light red bags contain 1 bright white bag, 2 muted yellow bags"
-    // contains  {"light red": {"bright white": 1, "muted yellow": 2} }
-    contains: HashMap<String, HashMap<String, i64>>,
+    // as edges light red -> bright white (1), light red -> muted yellow (2)
+    graph: BagGraph,
+    target: String,  // the bag the puzzle asks about
 }
 
 impl Haversacks {
-    // Read file with rules and set up <contains> HashMap
+    // Read file with rules and build the bag graph
     pub fn load(filename: &str) -> Haversacks {
         lazy_static! {
             static ref RULE_RE: Regex = Regex::new("^(.*) bags contain (.*)\\.$").unwrap();
             static ref BAG_COUNT_RE: Regex = Regex::new("([0-9]+) (.*) bag").unwrap();
         }
-        let mut contains = HashMap::new();
-        // let mut contained_in = HashMap::new();
+        let mut graph = BagGraph::new();
 
         // Read file
         let file = File::open(filename).unwrap();
@@ -30,87 +30,89 @@ impl Haversacks {
             let cap = RULE_RE.captures(&line).unwrap();
             let color = cap.get(1).unwrap().as_str();
             let contents = cap.get(2).unwrap().as_str();
-            let mut contents_map = HashMap::new();
+            graph.add_bag(color);
             for content_str in contents.split(',') {
                 let cap = BAG_COUNT_RE.captures(content_str);
                 match cap {
                     Some(cap) => {
                         let count = cap.get(1).unwrap().as_str().parse::<i64>().unwrap();
                         let color_b = cap.get(2).unwrap().as_str();
-                        contents_map.insert(String::from(color_b), count);
-                        // contained_in.insert(String::from(color_b), String::from(color));
+                        graph.add_rule(color, color_b, count);
                     }
                     None => {
                         // No other bags.
                     }
                 }
             }
-            contains.insert(String::from(color), contents_map);
         }
 
-        return Haversacks {contains: contains};
+        return Haversacks {graph: graph, target: String::from("shiny gold")};
     }
 
-    // Compute the number of bag colors that ultimately hold <color>
-    fn num_containers(&self, color: &str) -> i64 {
-        let mut to_expand = Vec::new();
-        to_expand.push(color);
-
-        let mut all_containers = HashMap::new();
-
-        while !to_expand.is_empty() {
-            let color = to_expand.pop().unwrap();
-            for (container_color, contents) in self.contains.iter() {
-                if contents.contains_key(color) {
-                    // <container_color> includes bags of <color> directly
-                    if !all_containers.contains_key(container_color) {
-                        // and we have registered this as a container yet
-                        all_containers.insert(container_color, true);
-                        to_expand.push(container_color);
-                    }
-                }
-            }
-        }
+    // Ask about a different bag than shiny gold.
+    pub fn with_target(mut self, color: &str) -> Haversacks {
+        self.target = String::from(color);
+        return self;
+    }
 
-        return all_containers.len() as i64;
+    pub fn graph(&self) -> &BagGraph {
+        return &self.graph;
     }
 
-    // Figure out how many bags are contained within a bag of <color>
-    fn num_within_helper(&self, color: &str, cache: &mut HashMap<String, i64>) -> i64 {
-        // If we've computed this before, take a shortcut.
-        if cache.contains_key(color) {
-            return cache[color];
+    // Describe what's inside the target bag, and how it can sit inside an <outer> bag.
+    pub fn explain(&self, outer: Option<&str>) -> String {
+        let mut text = String::new();
+        if self.graph.node(&self.target).is_none() {
+            return format!("No rules mention {} bags.\n", self.target);
         }
 
-        let mut n: i64 = 0;
+        match self.graph.bill_of_materials(&self.target) {
+            Ok(bom) => {
+                text += &format!("A {} bag holds:\n", self.target);
+                for (color, count) in &bom {
+                    text += &format!("  {} {}\n", count, color);
+                }
+                text += &format!("Deepest nesting: {}\n", self.graph.deepest_path(&self.target).unwrap().join(" > "));
+            }
+            Err(chain) => {
+                text += &format!("A {} bag holds endless bags: {}\n", self.target, chain.join(" > "));
+            }
+        }
 
-        let contents = &self.contains[color];
-        for (color_b, count) in contents {
-            n += count * (self.num_within(&color_b) + 1);
+        if let Some(outer) = outer {
+            let chains = self.graph.chains(outer, &self.target);
+            text += &format!("{} ways to find {} inside {}:\n", chains.len(), self.target, outer);
+            for chain in chains {
+                text += &format!("  {}\n", chain.join(" > "));
+            }
         }
 
-        // Store this so we don't compute it again.
-        cache.insert(String::from(color), n);
+        return text;
+    }
 
-        return n;
+    // Compute the number of bag colors that ultimately hold <color>
+    fn num_containers(&self, color: &str) -> i64 {
+        return self.graph.containers(color).len() as i64;
     }
 
     // Compute number of bags within a bag of <color>.
-    // (100% delegates the work to num_within_helper() after creating a cache to use.)
-    fn num_within(&self, color: &str) -> i64 {
-        let mut cache = HashMap::new();
+    fn num_within(&self, color: &str) -> Result<i64, Vec<String>> {
+        return self.graph.total_within(color);
+    }
 
-        return self.num_within_helper(color, &mut cache);
+    // Graphviz description of the rules.
+    pub fn to_dot(&self) -> String {
+        return self.graph.to_dot();
     }
 }
 
 impl super::Day for Haversacks {
     fn part1(&self) -> Result<i64, &str> {
-        return Ok(self.num_containers("shiny gold"));
+        return Ok(self.num_containers(&self.target));
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        return Ok(self.num_within("shiny gold"));
+        return self.num_within(&self.target).map_err(|_| "Bag rules are cyclic");
     }
 }
 
@@ -120,6 +122,46 @@ mod tests {
     use super::*;
     use crate::Day;
 
+    #[test]
+    fn test_examples() {
+        let tt = &Haversacks::load("data/day7_example1.txt");
+        assert_eq!(tt.part1(), Ok(4));
+        assert_eq!(tt.part2(), Ok(32));
+
+        let tt = &Haversacks::load("data/day7_example2.txt");
+        assert_eq!(tt.part2(), Ok(126));
+    }
+
+    #[test]
+    fn test_cyclic() {
+        let tt = &Haversacks::load("data/day7_cycle.txt");
+        assert_eq!(tt.part2(), Err("Bag rules are cyclic"));
+        assert_eq!(tt.num_within("shiny gold").err().unwrap(),
+                   vec!["shiny gold", "dark olive", "faded blue", "shiny gold"]);
+        assert!(tt.to_dot().contains("\"faded blue\" -> \"shiny gold\" [label=\"1\"];"));
+    }
+
+    #[test]
+    fn test_target() {
+        let tt = Haversacks::load("data/day7_example1.txt").with_target("muted yellow");
+        assert_eq!(tt.part1(), Ok(2));
+        assert_eq!(tt.part2(), Ok(2 * 33 + 9));
+    }
+
+    #[test]
+    fn test_explain() {
+        let tt = Haversacks::load("data/day7_example1.txt");
+        let text = tt.explain(Some("light red"));
+        assert!(text.starts_with("A shiny gold bag holds:\n  1 dark olive\n  16 dotted black\n"));
+        assert!(text.contains("Deepest nesting: shiny gold > "));
+        assert!(text.contains("2 ways to find shiny gold inside light red:\n"));
+        assert!(text.contains("  light red > muted yellow > shiny gold\n"));
+
+        let tt = Haversacks::load("data/day7_cycle.txt");
+        assert!(tt.explain(None).starts_with("A shiny gold bag holds endless bags: "));
+        assert_eq!(tt.with_target("mauve").explain(None), "No rules mention mauve bags.\n");
+    }
+
     #[test]
     fn test_part1() {
         let tt = &Haversacks::load("data/day7_input.txt");
@@ -0,0 +1,28 @@
+// Advent of Code 2020 solutions.  Each day is a module with a type that
+// implements Day; shared helpers live alongside them.
+
+pub mod report_repair;
+pub mod password_philosophy;
+pub mod toboggan_trajectory;
+pub mod passport_processing;
+pub mod binary_boarding;
+pub mod custom_customs;
+pub mod handy_haversacks;
+pub mod handheld_halting;
+pub mod encoding_error;
+pub mod adapter_array;
+pub mod seating_system;
+pub mod rain_risk;
+pub mod shuttle_search;
+pub mod docking_data;
+pub mod rambunctious_recitation;
+pub mod ticket_translation;
+pub mod number_theory;
+pub mod schema;
+pub mod bag_graph;
+
+pub trait Day {
+    // fn load(filename: &str) -> &dyn Day;
+    fn part1(&self) -> Result<i64, &str> ;
+    fn part2(&self) -> Result<i64, &str> ;
+}
@@ -1,65 +1,107 @@
-mod report_repair;
-mod password_philosophy;
-mod toboggan_trajectory;
-mod passport_processing;
-mod binary_boarding;
-mod custom_customs;
-mod handy_haversacks;
-mod handheld_halting;
-mod encoding_error;
-mod adapter_array;
-mod seating_system;
-mod rain_risk;
-mod shuttle_search;
-mod docking_data;
-mod rambunctious_recitation;
-mod ticket_translation;
-
-use report_repair::ReportRepair;
-use password_philosophy::PasswordPhilosophy;
-use toboggan_trajectory::TobogganTrajectory;
-use passport_processing::PassportProcessor;
-use binary_boarding::Boarding;
-use custom_customs::Customs;
-use handy_haversacks::Haversacks;
-use handheld_halting::Halting;
-use encoding_error::EncodingError;
-use adapter_array::AdapterArray;
-use seating_system::SeatingSystem;
-use rain_risk::RainRisk;
-use shuttle_search::ShuttleSearch;
-use docking_data::DockingData;
-use rambunctious_recitation::Recitation;
-use ticket_translation::TicketTranslation;
-
-pub trait Day {
-    // fn load(filename: &str) -> &dyn Day;
-    fn part1(&self) -> Result<i64, &str> ;
-    fn part2(&self) -> Result<i64, &str> ;
+use rs_aoc2020::report_repair::ReportRepair;
+use rs_aoc2020::password_philosophy::PasswordPhilosophy;
+use rs_aoc2020::toboggan_trajectory::TobogganTrajectory;
+use rs_aoc2020::passport_processing::PassportProcessor;
+use rs_aoc2020::binary_boarding::Boarding;
+use rs_aoc2020::custom_customs::Customs;
+use rs_aoc2020::handy_haversacks::Haversacks;
+use rs_aoc2020::handheld_halting::Halting;
+use rs_aoc2020::encoding_error::EncodingError;
+use rs_aoc2020::adapter_array::AdapterArray;
+use rs_aoc2020::seating_system::SeatingSystem;
+use rs_aoc2020::rain_risk::RainRisk;
+use rs_aoc2020::shuttle_search::ShuttleSearch;
+use rs_aoc2020::docking_data::DockingData;
+use rs_aoc2020::rambunctious_recitation::Recitation;
+use rs_aoc2020::ticket_translation::TicketTranslation;
+use rs_aoc2020::{Day, encoding_error, toboggan_trajectory};
+use rusttype::Point;
+
+const USAGE: &str = "Usage: rs_aoc2020 [DAY] [--slopes RIGHT/DOWN,...] [--bag COLOR [--inside COLOR]] [--preamble N]";
+
+// Settings from the command line.
+pub struct Options {
+    day: Option<usize>,                 // run just this day
+    slopes: Option<Vec<Point<u32>>>,    // day 3, part 2 slopes
+    bag: Option<String>,                // day 7, bag to ask about and explain
+    inside: Option<String>,             // day 7, outer bag to find chains from
+    preamble: Option<usize>,            // day 9, preamble length
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { day: None, slopes: None, bag: None, inside: None, preamble: None };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--slopes" => {
+                let value = args.next().ok_or("--slopes needs a value")?;
+                options.slopes = Some(toboggan_trajectory::parse_slopes(value)?);
+            }
+            "--bag" => {
+                options.bag = Some(args.next().ok_or("--bag needs a color")?.clone());
+            }
+            "--inside" => {
+                options.inside = Some(args.next().ok_or("--inside needs a color")?.clone());
+            }
+            "--preamble" => {
+                let value = args.next().ok_or("--preamble needs a length")?;
+                match value.parse::<usize>() {
+                    Ok(len) if len >= 2 => options.preamble = Some(len),
+                    _ => return Err(format!("Bad preamble length '{}'", value)),
+                }
+            }
+            _ => {
+                match arg.parse::<usize>() {
+                    Ok(day) if day >= 1 && options.day.is_none() => options.day = Some(day),
+                    _ => return Err(format!("Unexpected argument '{}'", arg)),
+                }
+            }
+        }
+    }
+
+    if options.inside.is_some() && options.bag.is_none() {
+        return Err(String::from("--inside needs --bag"));
+    }
+
+    return Ok(options);
 }
 
-pub fn run(n: Option<usize>) {
+pub fn run(options: &Options) {
+    let mut toboggan = TobogganTrajectory::load("data/day3_input.txt");
+    if let Some(slopes) = &options.slopes {
+        toboggan = toboggan.with_slopes(slopes.clone()).unwrap();
+    }
+
+    let mut haversacks = Haversacks::load("data/day7_input.txt");
+    if let Some(color) = &options.bag {
+        haversacks = haversacks.with_target(color);
+    }
+
     // Create array of days.  Each entry references a Day.
     let days: [&dyn Day; 16] = [
         &ReportRepair::load("data/day1_input.txt"),
-        &PasswordPhilosophy::load("data/day2_input.txt"),
-        &TobogganTrajectory::load("data/day3_input.txt"),
+        &PasswordPhilosophy::load("data/day2_input.txt").unwrap(),
+        &toboggan,
         &PassportProcessor::load("data/day4_input.txt"),
-        &Boarding::load("data/day5_input.txt"),
+        &Boarding::load("data/day5_input.txt").unwrap(),
         &Customs::load("data/day6_input.txt"),
-        &Haversacks::load("data/day7_input.txt"),
+        &haversacks,
         &Halting::load("data/day8_input.txt"),
-        &EncodingError::load("data/day9_input.txt", encoding_error::PREAMBLE_LEN),
+        &EncodingError::load("data/day9_input.txt", options.preamble.unwrap_or(encoding_error::PREAMBLE_LEN)),
         &AdapterArray::load("data/day10_input.txt"),
         &SeatingSystem::load("data/day11_input.txt"),
-        &RainRisk::load("data/day12_input.txt"),
-        &ShuttleSearch::load("data/day13_input.txt"),
-        &DockingData::load("data/day14_input.txt"),
+        &RainRisk::load("data/day12_input.txt").unwrap(),
+        &ShuttleSearch::load("data/day13_input.txt").unwrap(),
+        &DockingData::load("data/day14_input.txt").unwrap(),
         &Recitation::load("data/day15_input.txt"),
         &TicketTranslation::load("data/day16_input.txt"),
     ];
 
-    match n {
+    match options.day {
+        Some(day_no) if day_no > days.len() => {
+            println!("Day {} isn't solved yet.", day_no);
+        }
         Some(day_no) => {
             // Run for one day.
             match days[day_no-1].part1() {
@@ -90,10 +132,23 @@ pub fn run(n: Option<usize>) {
             }
         }
     }
+
+    // Explain the day 7 bag asked about.
+    if options.bag.is_some() && (options.day.is_none() || options.day == Some(7)) {
+        print!("{}", haversacks.explain(options.inside.as_deref()));
+    }
 }
 
 fn main() {
     println!("Advent of Code 2020.");
 
-    run(None);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(options) => run(&options),
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,114 @@
+// Integer helpers shared by the puzzles: extended GCD and the Chinese Remainder Theorem.
+// Everything works in i128 and uses checked arithmetic, so large moduli report
+// an error rather than silently wrapping.
+
+// Extended Euclid.  Returns (g, x, y) with a*x + b*y = g = gcd(a, b), g >= 0.
+pub fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        let tmp = old_r - q * r;
+        old_r = r;
+        r = tmp;
+        let tmp = old_x - q * x;
+        old_x = x;
+        x = tmp;
+        let tmp = old_y - q * y;
+        old_y = y;
+        y = tmp;
+    }
+
+    if old_r < 0 {
+        return (-old_r, -old_x, -old_y);
+    }
+    return (old_r, old_x, old_y);
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    return ext_gcd(a, b).0;
+}
+
+// Multiplicative inverse of a modulo m, if a and m are coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = ext_gcd(a.rem_euclid(m), m);
+    if g != 1 {
+        return None;
+    }
+    return Some(x.rem_euclid(m));
+}
+
+// Solve the system x = residue (mod modulus) for every (residue, modulus) pair.
+// Moduli need not be coprime.  Returns (x, lcm) where x is the smallest
+// non-negative solution and every solution is x + k*lcm.
+pub fn crt(constraints: &[(i128, i128)]) -> Result<(i128, i128), &'static str> {
+    let mut x: i128 = 0;
+    let mut lcm: i128 = 1;
+
+    for (residue, modulus) in constraints {
+        if *modulus <= 0 {
+            return Err("modulus must be positive");
+        }
+
+        // Merge x (mod lcm) with residue (mod modulus).
+        let (g, p, _) = ext_gcd(lcm, *modulus);
+        let diff = (*residue - x).rem_euclid(*modulus);
+        if diff % g != 0 {
+            return Err("no solution");
+        }
+
+        let step = *modulus / g;
+        let k = (diff / g).checked_mul(p.rem_euclid(step))
+            .ok_or("overflow")?
+            .rem_euclid(step);
+        let new_lcm = lcm.checked_mul(step).ok_or("overflow")?;
+        x = lcm.checked_mul(k)
+            .and_then(|v| v.checked_add(x))
+            .ok_or("overflow")?
+            .rem_euclid(new_lcm);
+        lcm = new_lcm;
+    }
+
+    return Ok((x, lcm));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ext_gcd() {
+        assert_eq!(ext_gcd(240, 46), (2, -9, 47));
+        assert_eq!(gcd(17, 5), 1);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(4, 6), None);
+    }
+
+    #[test]
+    fn test_crt_coprime() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        assert_eq!(crt(&[]), Ok((0, 1)));
+    }
+
+    #[test]
+    fn test_crt_non_coprime() {
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Ok((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), Err("no solution"));
+    }
+
+    #[test]
+    fn test_crt_large() {
+        // Two primes near 2^61; their product doesn't fit in i64.
+        let p: i128 = 2305843009213693951;
+        let q: i128 = 2305843009213693921;
+        let (x, lcm) = crt(&[(5, p), (7, q)]).unwrap();
+        assert_eq!(lcm, p * q);
+        assert_eq!(x % p, 5);
+        assert_eq!(x % q, 7);
+
+        assert_eq!(crt(&[(0, p), (0, q), (0, p - 2)]), Err("overflow"));
+    }
+}
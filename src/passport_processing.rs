@@ -1,8 +1,7 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::collections::HashMap;
-use lazy_static::lazy_static;
-use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use crate::schema::Schema;
 
 struct Passport {
     content: HashMap<String, String>,
@@ -20,145 +19,96 @@ impl Passport {
     fn set(&mut self, key: &str, value: &str) {
         self.content.insert(String::from(key), String::from(value));
     }
+}
 
-    fn fields_present(&self) -> bool {
-        static REQUIRED_FIELDS: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
-
-        for k in REQUIRED_FIELDS.iter() {
-            if !self.content.contains_key(*k) {
-                return false;
-            }
-        }
+// Why one passport was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    pub record: usize,                     // 1-based position in the input
+    pub missing: Vec<String>,              // required fields not present
+    pub failures: Vec<(String, String)>,   // (field, reason) for values that fail their check
+}
 
-        return true;
+impl Diagnosis {
+    pub fn is_valid(&self) -> bool {
+        return self.missing.is_empty() && self.failures.is_empty();
     }
+}
 
-    fn valid_year(&self, key: &str, min: u32, max: u32) -> bool {
-        lazy_static! {
-            static ref YEAR_RE: Regex = Regex::new("^([0-9]{4})$").unwrap();
-        }
-
-        let year_str = self.content.get(key).unwrap();
+// Number of passports with each kind of problem in a field.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FieldCounts {
+    pub missing: usize,
+    pub invalid: usize,
+}
 
-        if !YEAR_RE.is_match(year_str) {
-            return false;
-        }
+pub struct Report {
+    pub passports: Vec<Diagnosis>,
+}
 
-        let year: u32 = year_str.parse().unwrap();
-        return (year >= min) & (year <= max);
+impl Report {
+    pub fn valid(&self) -> usize {
+        return self.passports.iter().filter(|d| d.is_valid()).count();
     }
 
-    fn valid_hgt(&self) -> bool {
-        lazy_static! {
-            static ref HGT_RE: Regex = Regex::new("^([0-9]+)([a-z]{2})$").unwrap();
-        }
-
-        let retval: bool;
-
-        match HGT_RE.captures(self.content.get("hgt").unwrap()) {
-            Some(cap) => {
-                let value:u32 = cap.get(1).unwrap().as_str().parse().unwrap();
-                let units:&str = cap.get(2).unwrap().as_str();
-                if units == "cm" {
-                    // range check height in cm
-                    retval = (value >= 150) & (value <= 193)
-                } else if units == "in" {
-                    // range check height in in
-                    retval = (value >= 59) & (value <= 76)
-                } else {
-                    // invalid units
-                    retval = false
-                }
+    // Problem counts for each field that had any, by field name.
+    pub fn by_field(&self) -> BTreeMap<String, FieldCounts> {
+        let mut counts: BTreeMap<String, FieldCounts> = BTreeMap::new();
+        for diagnosis in &self.passports {
+            for field in &diagnosis.missing {
+                counts.entry(field.clone()).or_default().missing += 1;
             }
-            None => {
-                retval = false;
+            for (field, _) in &diagnosis.failures {
+                counts.entry(field.clone()).or_default().invalid += 1;
             }
         }
 
-        return retval;
-    }
-
-    fn valid_hcl(&self) -> bool {
-        lazy_static! {
-            static ref HCL_RE: Regex = Regex::new("^#([0-9a-f]{6})$").unwrap();
-        }
-
-        return HCL_RE.is_match(self.content.get("hcl").unwrap());
+        return counts;
     }
 
-    fn valid_ecl(&self) -> bool {
-        static VALID_ECL: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
-
-        let ecl = self.content.get("ecl").unwrap();
-        for valid_ecl in VALID_ECL.iter() {
-            if ecl == valid_ecl {
-                return true;
+    // One row per problem found.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("record,field,problem,reason\n");
+        for diagnosis in &self.passports {
+            for field in &diagnosis.missing {
+                csv += &format!("{},{},missing,\n", diagnosis.record, field);
+            }
+            for (field, reason) in &diagnosis.failures {
+                csv += &format!("{},{},invalid,\"{}\"\n", diagnosis.record, field, reason.replace('"', "\"\""));
             }
         }
 
-        return false;
-    }
-
-    fn valid_pid(&self) -> bool {
-        lazy_static! {
-            static ref PID_RE: Regex = Regex::new("^([0-9]{9})$").unwrap();
-        }
-
-        return PID_RE.is_match(self.content.get("pid").unwrap());
+        return csv;
     }
 
-    fn fields_valid(&self) -> bool {
-        // validate byr
-        if !self.valid_year("byr", 1920, 2002) {
-            return false;
-        }
-
-        // validate iyr
-        if !self.valid_year("iyr", 2010, 2020) {
-            return false;
-        }
-
-        // validate eyr
-        if !self.valid_year("eyr", 2020, 2030) {
-            // println!("Bad eyr: {}", self.content.get("eyr").unwrap());
-            return false;
-        }
-
-        // validate hgt
-        if !self.valid_hgt() {
-            return false;
-        }
-
-        // validate hcl
-        if !self.valid_hcl() {
-            return false;
+    // One row per field with problem counts.
+    pub fn summary_csv(&self) -> String {
+        let mut csv = String::from("field,missing,invalid\n");
+        for (field, counts) in self.by_field() {
+            csv += &format!("{},{},{}\n", field, counts.missing, counts.invalid);
         }
 
-        // validate ecl
-        if !self.valid_ecl() {
-            return false;
-        }
-
-        // validate pid
-        if !self.valid_pid() {
-            return false;
-        }
-
-        return true;
+        return csv;
     }
 }
 
 pub struct PassportProcessor {
+    schema: Schema,
     passports: Vec<Passport>,
 }
 
 impl PassportProcessor {
-    fn new() -> PassportProcessor {
-        PassportProcessor {passports: Vec::new() }
+    fn new(schema: Schema) -> PassportProcessor {
+        PassportProcessor {schema: schema, passports: Vec::new() }
     }
 
     pub fn load(filename: &str) -> PassportProcessor {
-        let mut proc = PassportProcessor::new();
+        return PassportProcessor::load_with_schema(filename, Schema::passport());
+    }
+
+    // Load documents to be checked against some other schema.
+    pub fn load_with_schema(filename: &str, schema: Schema) -> PassportProcessor {
+        let mut proc = PassportProcessor::new(schema);
 
         let file = File::open(filename).unwrap();
         let reader = BufReader::new(file);
@@ -192,6 +142,22 @@ impl PassportProcessor {
         return proc;
     }
 
+    // Check every passport, recording what's wrong with each.
+    pub fn diagnose(&self) -> Report {
+        let mut passports: Vec<Diagnosis> = Vec::new();
+        for (n, passport) in self.passports.iter().enumerate() {
+            let missing = self.schema.missing(&passport.content);
+            let failures = self.schema.failures(&passport.content);
+            passports.push(Diagnosis {
+                record: n+1,
+                missing: missing.iter().map(|f| f.to_string()).collect(),
+                failures: failures.into_iter().map(|(f, reason)| (f.to_string(), reason)).collect(),
+            });
+        }
+
+        return Report { passports: passports };
+    }
+
     fn store(&mut self, passport: Passport) {
         self.passports.push(passport);
         // println!("Stored record.");
@@ -202,7 +168,7 @@ impl super::Day for PassportProcessor {
     fn part1(&self) -> Result<i64, &str> {
         let mut valid = 0;
         for passport in self.passports.iter() {
-            if passport.fields_present() {
+            if self.schema.is_complete(&passport.content) {
                 valid += 1;
             }
         }
@@ -211,16 +177,7 @@ impl super::Day for PassportProcessor {
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        let mut valid = 0;
-        for passport in self.passports.iter() {
-            if passport.fields_present() {
-                if passport.fields_valid() {
-                    valid += 1;
-                }
-            }
-        }
-
-        return Ok(valid);
+        return Ok(self.diagnose().valid() as i64);
     }
 }
 
@@ -230,6 +187,52 @@ mod tests {
     use super::*;
     use crate::Day;
 
+    #[test]
+    fn test_examples() {
+        let tt = &PassportProcessor::load("data/day4_example1.txt");
+        assert_eq!(tt.part1(), Ok(2));
+
+        let tt = &PassportProcessor::load("data/day4_example2.txt");
+        assert_eq!(tt.part2(), Ok(4));
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let tt = &PassportProcessor::load("data/day4_example2.txt");
+        let report = tt.diagnose();
+        assert_eq!(report.passports.len(), 8);
+        assert_eq!(report.valid(), 4);
+
+        let first = &report.passports[0];
+        assert_eq!(first.record, 1);
+        assert!(first.missing.is_empty());
+        assert!(first.failures.contains(&(String::from("hgt"), String::from("170 has unknown unit ''"))));
+        assert!(first.failures.contains(&(String::from("eyr"), String::from("1972 out of range 2020-2030"))));
+
+        let tt = &PassportProcessor::load("data/day4_example1.txt");
+        let report = tt.diagnose();
+        assert_eq!(report.passports[1].missing, vec!["hgt"]);
+        assert_eq!(report.by_field()["hgt"], FieldCounts { missing: 1, invalid: 0 });
+        assert_eq!(report.by_field()["byr"], FieldCounts { missing: 1, invalid: 0 });
+    }
+
+    #[test]
+    fn test_report_csv() {
+        let tt = &PassportProcessor::load("data/day4_example1.txt");
+        let report = tt.diagnose();
+        assert_eq!(report.to_csv(),
+                   "record,field,problem,reason\n2,hgt,missing,\n4,byr,missing,\n");
+        assert_eq!(report.summary_csv(), "field,missing,invalid\nbyr,1,0\nhgt,1,0\n");
+    }
+
+    #[test]
+    fn test_other_schema() {
+        let schema = Schema::parse("[ecl]\nrequired = true\ntype = \"enum\"\nvalues = [\"amb\", \"gry\"]\n").unwrap();
+        let tt = &PassportProcessor::load_with_schema("data/day4_example1.txt", schema);
+        assert_eq!(tt.part1(), Ok(4));
+        assert_eq!(tt.part2(), Ok(2));
+    }
+
     #[test]
     fn test_part1() {
         let tt = &PassportProcessor::load("data/day4_input.txt");
@@ -3,31 +3,226 @@ use std::io::{BufRead, BufReader};
 use lazy_static::lazy_static;
 use regex::Regex;
 
-struct Entry {
-    n1: usize,
-    n2: usize,
-    letter: char,
-    password: String,
-}
-
-impl Entry {
-    fn is_valid1(&self) -> bool {
-        let mut count = 0;
-        for c in self.password.chars() {
-            if c == self.letter {
-                count += 1;
+pub struct Entry {
+    pub n1: usize,
+    pub n2: usize,
+    pub letter: char,
+    pub password: String,
+}
+
+// A rule that passwords must follow.
+pub trait PasswordPolicy {
+    fn name(&self) -> String;
+    // Ok if the entry's password satisfies the policy, otherwise the reason it doesn't.
+    fn check(&self, entry: &Entry) -> Result<(), String>;
+}
+
+// Part 1: the letter must appear between n1 and n2 times.
+pub struct SledRentalPolicy;
+
+impl PasswordPolicy for SledRentalPolicy {
+    fn name(&self) -> String {
+        return String::from("sled");
+    }
+
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let count = entry.password.chars().filter(|c| *c == entry.letter).count();
+        if count < entry.n1 || count > entry.n2 {
+            return Err(format!("'{}' appears {} times, expected {}-{}", entry.letter, count, entry.n1, entry.n2));
+        }
+        return Ok(());
+    }
+}
+
+// Part 2: the letter must be at exactly one of positions n1 and n2 (1-based).
+pub struct TobogganPolicy;
+
+impl PasswordPolicy for TobogganPolicy {
+    fn name(&self) -> String {
+        return String::from("toboggan");
+    }
+
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let at = |pos: usize| match pos.checked_sub(1) {
+            Some(index) => Ok(entry.password.chars().nth(index) == Some(entry.letter)),
+            None => Err(String::from("position 0 is not valid")),
+        };
+        let at1 = at(entry.n1)?;
+        let at2 = at(entry.n2)?;
+        if at1 == at2 {
+            let matches = if at1 { "both" } else { "neither" };
+            return Err(format!("'{}' at {} of positions {} and {}", entry.letter, matches, entry.n1, entry.n2));
+        }
+        return Ok(());
+    }
+}
+
+// Password length must be within min..=max.
+pub struct LengthPolicy {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PasswordPolicy for LengthPolicy {
+    fn name(&self) -> String {
+        return format!("length:{}-{}", self.min, self.max);
+    }
+
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let len = entry.password.chars().count();
+        if len < self.min || len > self.max {
+            return Err(format!("length {} outside {}-{}", len, self.min, self.max));
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    fn name(&self) -> &str {
+        match self {
+            CharClass::Lower => "lower",
+            CharClass::Upper => "upper",
+            CharClass::Digit => "digit",
+            CharClass::Symbol => "symbol",
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::Symbol => !c.is_alphanumeric(),
+        }
+    }
+}
+
+// Password must contain at least one character of each class.
+pub struct ClassesPolicy {
+    pub classes: Vec<CharClass>,
+}
+
+impl PasswordPolicy for ClassesPolicy {
+    fn name(&self) -> String {
+        let names: Vec<&str> = self.classes.iter().map(|c| c.name()).collect();
+        return format!("classes:{}", names.join(","));
+    }
+
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let mut lacking: Vec<&str> = Vec::new();
+        for class in &self.classes {
+            if !entry.password.chars().any(|c| class.matches(c)) {
+                lacking.push(class.name());
+            }
+        }
+        if !lacking.is_empty() {
+            return Err(format!("no {} characters", lacking.join(" or ")));
+        }
+        return Ok(());
+    }
+}
+
+// No character may repeat more than max_run times in a row.
+pub struct RepeatsPolicy {
+    pub max_run: usize,
+}
+
+impl PasswordPolicy for RepeatsPolicy {
+    fn name(&self) -> String {
+        return format!("repeats:{}", self.max_run);
+    }
+
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let mut run = 0;
+        let mut last: Option<char> = None;
+        for c in entry.password.chars() {
+            run = if last == Some(c) { run + 1 } else { 1 };
+            last = Some(c);
+            if run > self.max_run {
+                return Err(format!("'{}' repeated more than {} times", c, self.max_run));
             }
         }
+        return Ok(());
+    }
+}
+
+// Password must match a regular expression.
+pub struct RegexPolicy {
+    pub re: Regex,
+}
 
-        return (count >= self.n1) & (count <= self.n2);
+impl PasswordPolicy for RegexPolicy {
+    fn name(&self) -> String {
+        return format!("regex:{}", self.re.as_str());
     }
 
-    fn is_valid2(&self) -> bool {
-        return (self.password.chars().nth(self.n1-1).unwrap() == self.letter) ^
-               (self.password.chars().nth(self.n2-1).unwrap() == self.letter);
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        if !self.re.is_match(&entry.password) {
+            return Err(format!("does not match {}", self.re.as_str()));
+        }
+        return Ok(());
     }
 }
 
+// Build a policy from a spec such as "sled", "toboggan", "length:8-20",
+// "classes:lower,digit", "repeats:2" or "regex:^[a-z]+$".
+pub fn policy(spec: &str) -> Result<Box<dyn PasswordPolicy>, String> {
+    let (kind, arg) = match spec.find(':') {
+        Some(n) => (&spec[..n], &spec[n+1..]),
+        None => (spec, ""),
+    };
+    let bad = || format!("invalid policy '{}'", spec);
+
+    let policy: Box<dyn PasswordPolicy> = match kind {
+        "sled" => Box::new(SledRentalPolicy),
+        "toboggan" => Box::new(TobogganPolicy),
+        "length" => {
+            let parts: Vec<&str> = arg.split('-').collect();
+            if parts.len() != 2 {
+                return Err(bad());
+            }
+            let min = parts[0].parse().map_err(|_| bad())?;
+            let max = parts[1].parse().map_err(|_| bad())?;
+            Box::new(LengthPolicy { min: min, max: max })
+        }
+        "classes" => {
+            let mut classes: Vec<CharClass> = Vec::new();
+            for name in arg.split(',') {
+                classes.push(match name {
+                    "lower" => CharClass::Lower,
+                    "upper" => CharClass::Upper,
+                    "digit" => CharClass::Digit,
+                    "symbol" => CharClass::Symbol,
+                    _ => return Err(bad()),
+                });
+            }
+            Box::new(ClassesPolicy { classes: classes })
+        }
+        "repeats" => Box::new(RepeatsPolicy { max_run: arg.parse().map_err(|_| bad())? }),
+        "regex" => Box::new(RegexPolicy { re: Regex::new(arg).map_err(|e| e.to_string())? }),
+        _ => return Err(bad()),
+    };
+
+    return Ok(policy);
+}
+
+// An entry that broke a policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub line: usize,  // 1-based line in the input
+    pub password: String,
+    pub policy: String,
+    pub reason: String,
+}
+
 pub struct PasswordPhilosophy {
     // a list of passwords and policies
     entries: Vec<Entry>,
@@ -36,63 +231,76 @@ pub struct PasswordPhilosophy {
 
 
 impl PasswordPhilosophy {
-    fn process_line(s: &str) -> Entry {
+    fn process_line(s: &str) -> Result<Entry, String> {
         // println! ("Processing '{}'", s);
 
         lazy_static! {
-            static ref ENTRY_RE: Regex = Regex::new("^([0-9]+)-([0-9]+) ([a-z]): ([a-z]+)").unwrap();
+            static ref ENTRY_RE: Regex = Regex::new(r"^([0-9]+)-([0-9]+) (\S): (\S+)$").unwrap();
         }
 
-        let cap = ENTRY_RE.captures(s).unwrap();
+        let cap = match ENTRY_RE.captures(s) {
+            Some(cap) => cap,
+            None => return Err(format!("'{}' isn't like '1-3 a: password'", s)),
+        };
+
+        let number = |n: usize| cap.get(n).unwrap().as_str().parse::<usize>()
+            .map_err(|e| format!("'{}' has a bad number: {}", s, e));
 
-        return Entry {
-            n1: cap.get(1).unwrap().as_str().parse::<usize>().unwrap(),
-            n2: cap.get(2).unwrap().as_str().parse::<usize>().unwrap(),
+        return Ok(Entry {
+            n1: number(1)?,
+            n2: number(2)?,
             letter: cap.get(3).unwrap().as_str().chars().nth(0).unwrap(),
             password: String::from(cap.get(4).unwrap().as_str()),
-            };
+            });
     }
 
-    pub fn load(filename: &str) -> PasswordPhilosophy {
+    pub fn load(filename: &str) -> Result<PasswordPhilosophy, String> {
         // Create the Day2 value
         let mut password_db = PasswordPhilosophy { entries: Vec::new() };
 
         let file = File::open(filename).unwrap();
         let reader = BufReader::new(file);
 
-        for line in reader.lines() {
+        for (n, line) in reader.lines().enumerate() {
             let line = line.unwrap();
-            let entry = Self::process_line(&line.as_str());
+            let entry = Self::process_line(line.trim_end()).map_err(|e| format!("line {}: {}", n+1, e))?;
             password_db.entries.push(entry);
         }
 
-        return password_db;
+        return Ok(password_db);
     }
-}
 
-impl super::Day for PasswordPhilosophy {
-    fn part1(&self) -> Result<i64, &str> {
-        let mut valid_entries = 0;
+    pub fn count_valid(&self, policy: &dyn PasswordPolicy) -> usize {
+        return self.entries.iter().filter(|e| policy.check(e).is_ok()).count();
+    }
 
-        for entry in &self.entries {
-            if entry.is_valid1() {
-                valid_entries += 1;
+    // Every (entry, policy) pair where the entry fails the policy.
+    pub fn violations(&self, policies: &[Box<dyn PasswordPolicy>]) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = Vec::new();
+        for (n, entry) in self.entries.iter().enumerate() {
+            for policy in policies {
+                if let Err(reason) = policy.check(entry) {
+                    violations.push(Violation {
+                        line: n+1,
+                        password: entry.password.clone(),
+                        policy: policy.name(),
+                        reason: reason,
+                    });
+                }
             }
         }
 
-        return Ok(valid_entries);
+        return violations;
     }
+}
 
-    fn part2(&self) -> Result<i64, &str> {
-        let mut valid_entries = 0;
-
-        for entry in &self.entries {
-            if entry.is_valid2() {
-                valid_entries += 1;
-            }
-        }
+impl super::Day for PasswordPhilosophy {
+    fn part1(&self) -> Result<i64, &str> {
+        return Ok(self.count_valid(&SledRentalPolicy) as i64);
+    }
 
-        return Ok(valid_entries);
+    fn part2(&self) -> Result<i64, &str> {
+        return Ok(self.count_valid(&TobogganPolicy) as i64);
     }
 }
 
@@ -101,16 +309,83 @@ mod tests {
     use super::*;
     use crate::Day;
 
+    #[test]
+    fn test_pp_examples() {
+        let pp = &PasswordPhilosophy::load("data/day2_example.txt").unwrap();
+
+        assert_eq!(pp.part1(), Ok(2));
+        assert_eq!(pp.part2(), Ok(1));
+    }
+
+    #[test]
+    fn test_policies() {
+        let pp = &PasswordPhilosophy::load("data/day2_example.txt").unwrap();
+
+        assert_eq!(pp.count_valid(&*policy("length:5-9").unwrap()), 3);
+        assert_eq!(pp.count_valid(&*policy("length:6-9").unwrap()), 1);
+        assert_eq!(pp.count_valid(&*policy("classes:lower").unwrap()), 3);
+        assert_eq!(pp.count_valid(&*policy("classes:digit").unwrap()), 0);
+        assert_eq!(pp.count_valid(&*policy("repeats:2").unwrap()), 2);
+        assert_eq!(pp.count_valid(&*policy("regex:^a").unwrap()), 1);
+        assert!(policy("length:5").is_err());
+        assert!(policy("classes:emoji").is_err());
+        assert!(policy("whatever").is_err());
+    }
+
+    #[test]
+    fn test_violations() {
+        let pp = &PasswordPhilosophy::load("data/day2_example.txt").unwrap();
+        let policies = vec![policy("sled").unwrap(), policy("toboggan").unwrap()];
+        let violations = pp.violations(&policies);
+
+        assert_eq!(violations.len(), 3);
+        assert_eq!(violations[0], Violation {
+            line: 2,
+            password: String::from("cdefg"),
+            policy: String::from("sled"),
+            reason: String::from("'b' appears 0 times, expected 1-3"),
+        });
+        assert_eq!(violations[2].policy, "toboggan");
+        assert_eq!(violations[2].reason, "'c' at both of positions 2 and 9");
+    }
+
+    #[test]
+    fn test_load() {
+        let pp = &PasswordPhilosophy::load("data/day2_mixed.txt").unwrap();
+        assert_eq!(pp.entries[0].password, "abC1");
+        assert_eq!(pp.entries[2].password, "c1c!c");
+        assert_eq!(pp.count_valid(&*policy("classes:upper,digit").unwrap()), 1);
+        assert_eq!(pp.count_valid(&*policy("classes:symbol").unwrap()), 1);
+        assert_eq!(pp.count_valid(&*policy("length:4-5").unwrap()), 2);
+        assert_eq!(pp.count_valid(&*policy("regex:[0-9]").unwrap()), 2);
+
+        assert_eq!(PasswordPhilosophy::load("data/day2_bad.txt").err(),
+                   Some(String::from("line 2: '1-3 b cdefg' isn't like '1-3 a: password'")));
+        assert_eq!(PasswordPhilosophy::process_line("99999999999999999999999-3 a: abc").err(),
+                   Some(String::from("'99999999999999999999999-3 a: abc' has a bad number: number too large to fit in target type")));
+    }
+
+    #[test]
+    fn test_position_zero() {
+        let pp = &PasswordPhilosophy::load("data/day2_mixed.txt").unwrap();
+        let violations = pp.violations(&[policy("toboggan").unwrap()]);
+
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].line, 2);
+        assert_eq!(violations[0].reason, "position 0 is not valid");
+        assert_eq!(pp.part2(), Ok(1));
+    }
+
     #[test]
     fn test_pp_part1() {
-        let pp = &PasswordPhilosophy::load("data/day2_input.txt");
+        let pp = &PasswordPhilosophy::load("data/day2_input.txt").unwrap();
 
         assert_eq!(pp.part1(), Result::Ok(625));
     }
 
     #[test]
     fn test_pp_part2() {
-        let pp = &PasswordPhilosophy::load("data/day2_input.txt");
+        let pp = &PasswordPhilosophy::load("data/day2_input.txt").unwrap();
 
         assert_eq!(pp.part2(), Result::Ok(391));
     }
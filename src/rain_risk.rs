@@ -3,154 +3,289 @@ use std::io::BufRead;
 use std::io::BufReader;
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::fmt;
 
-struct Instruction {
-    op: String,
-    value: i32,
+// A navigation instruction.  Turns are counted in quarter turns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    North(i32),
+    South(i32),
+    East(i32),
+    West(i32),
+    Left(i32),
+    Right(i32),
+    Forward(i32),
 }
 
-pub struct RainRisk {
-    instructions: Vec<Instruction>,
+impl Action {
+    pub fn parse(s: &str) -> Result<Action, String> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new("^([A-Z])([0-9]+)$").unwrap();
+        }
+
+        let cap = match RE.captures(s.trim()) {
+            Some(cap) => cap,
+            None => return Err(format!("unrecognised instruction '{}'", s)),
+        };
+        let value: i32 = match cap[2].parse() {
+            Ok(value) => value,
+            Err(_) => return Err(format!("value out of range '{}'", s)),
+        };
+
+        let action = match &cap[1] {
+            "N" => Action::North(value),
+            "S" => Action::South(value),
+            "E" => Action::East(value),
+            "W" => Action::West(value),
+            "L" | "R" => {
+                if value % 90 != 0 {
+                    return Err(format!("rotation of {} degrees is not a right angle", value));
+                }
+                if &cap[1] == "L" { Action::Left(value / 90) } else { Action::Right(value / 90) }
+            }
+            "F" => Action::Forward(value),
+            op => return Err(format!("unrecognised operation '{}'", op)),
+        };
+
+        return Ok(action);
+    }
 }
 
-impl RainRisk {
-    pub fn load(filename: &str) -> RainRisk {
-        lazy_static! {
-            static ref RE: Regex = Regex::new("([NSEWLRF])([0-9]+)").unwrap();
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::North(n) => write!(f, "N{}", n),
+            Action::South(n) => write!(f, "S{}", n),
+            Action::East(n) => write!(f, "E{}", n),
+            Action::West(n) => write!(f, "W{}", n),
+            Action::Left(turns) => write!(f, "L{}", turns * 90),
+            Action::Right(turns) => write!(f, "R{}", turns * 90),
+            Action::Forward(n) => write!(f, "F{}", n),
+        }
+    }
+}
+
+// Where the ship is, which way it faces and where its waypoint is.
+// Heading is in quarter turns anticlockwise from East.  The waypoint is relative to the ship.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct State {
+    pub x: i32,
+    pub y: i32,
+    pub heading: i32,
+    pub waypoint_x: i32,
+    pub waypoint_y: i32,
+}
+
+impl State {
+    pub fn new() -> State {
+        State { x: 0, y: 0, heading: 0, waypoint_x: 10, waypoint_y: 1 }
+    }
+
+    pub fn manhattan(&self) -> i64 {
+        return (self.x.abs() + self.y.abs()) as i64;
+    }
+}
+
+impl Default for State {
+    fn default() -> State {
+        return State::new();
+    }
+}
+
+// How a navigator interprets the movement primitives.
+pub trait Strategy {
+    // N/S/E/W by (dx, dy)
+    fn translate(&self, state: &mut State, dx: i32, dy: i32);
+    // L/R by quarter turns, anticlockwise positive
+    fn rotate(&self, state: &mut State, turns: i32);
+    // F by n
+    fn forward(&self, state: &mut State, n: i32);
+}
+
+// Part 1: actions move and turn the ship itself.
+pub struct ShipStrategy;
+
+impl Strategy for ShipStrategy {
+    fn translate(&self, state: &mut State, dx: i32, dy: i32) {
+        state.x += dx;
+        state.y += dy;
+    }
+
+    fn rotate(&self, state: &mut State, turns: i32) {
+        state.heading = (state.heading + turns).rem_euclid(4);
+    }
+
+    fn forward(&self, state: &mut State, n: i32) {
+        let (dx, dy) = match state.heading {
+            0 => (1, 0),   // East
+            1 => (0, 1),   // North
+            2 => (-1, 0),  // West
+            _ => (0, -1),  // South
+        };
+        state.x += dx * n;
+        state.y += dy * n;
+    }
+}
+
+// Part 2: actions move and rotate the waypoint, F moves the ship toward it.
+pub struct WaypointStrategy;
+
+impl Strategy for WaypointStrategy {
+    fn translate(&self, state: &mut State, dx: i32, dy: i32) {
+        state.waypoint_x += dx;
+        state.waypoint_y += dy;
+    }
+
+    fn rotate(&self, state: &mut State, turns: i32) {
+        for _n in 0..turns.rem_euclid(4) {
+            let new_y = state.waypoint_x;
+            let new_x = -state.waypoint_y;
+            state.waypoint_x = new_x;
+            state.waypoint_y = new_y;
+        }
+    }
+
+    fn forward(&self, state: &mut State, n: i32) {
+        state.x += state.waypoint_x * n;
+        state.y += state.waypoint_y * n;
+    }
+}
+
+// Interprets actions using a movement strategy.
+pub struct Navigator<S: Strategy> {
+    strategy: S,
+    pub state: State,
+}
+
+impl<S: Strategy> Navigator<S> {
+    pub fn new(strategy: S) -> Navigator<S> {
+        Navigator { strategy: strategy, state: State::new() }
+    }
+
+    pub fn step(&mut self, action: &Action) {
+        let state = &mut self.state;
+        match *action {
+            Action::North(n) => self.strategy.translate(state, 0, n),
+            Action::South(n) => self.strategy.translate(state, 0, -n),
+            Action::East(n) => self.strategy.translate(state, n, 0),
+            Action::West(n) => self.strategy.translate(state, -n, 0),
+            Action::Left(turns) => self.strategy.rotate(state, turns),
+            Action::Right(turns) => self.strategy.rotate(state, -turns),
+            Action::Forward(n) => self.strategy.forward(state, n),
+        }
+    }
+
+    pub fn run(&mut self, actions: &[Action]) -> State {
+        for action in actions {
+            self.step(action);
         }
 
-        let mut instructions: Vec<Instruction> = Vec::new();
+        return self.state;
+    }
+}
+
+// The states a navigator passed through.  The first entry is the starting state,
+// each following entry is the state after the action it records.
+pub struct Track {
+    pub steps: Vec<(Option<Action>, State)>,
+}
+
+impl Track {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,action,x,y,heading,waypoint_x,waypoint_y\n");
+        for (n, (action, state)) in self.steps.iter().enumerate() {
+            let action = match action {
+                Some(action) => action.to_string(),
+                None => String::new(),
+            };
+            csv += &format!("{},{},{},{},{},{},{}\n", n, action, state.x, state.y,
+                            state.heading * 90, state.waypoint_x, state.waypoint_y);
+        }
+
+        return csv;
+    }
+
+    // Draw the ship's path, north up.  Start is marked green, end red.
+    pub fn to_svg(&self) -> String {
+        let xs = self.steps.iter().map(|(_, s)| s.x);
+        let ys = self.steps.iter().map(|(_, s)| -s.y);
+        let (min_x, max_x) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+        let (min_y, max_y) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0));
+        let margin = 1 + (max_x - min_x).max(max_y - min_y) / 20;
+
+        let mut points: Vec<String> = Vec::new();
+        for (_, state) in &self.steps {
+            points.push(format!("{},{}", state.x, -state.y));
+        }
+
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+                              min_x - margin, min_y - margin,
+                              max_x - min_x + 2 * margin, max_y - min_y + 2 * margin);
+        svg += &format!("  <polyline fill=\"none\" stroke=\"black\" stroke-width=\"1\" \
+                         vector-effect=\"non-scaling-stroke\" points=\"{}\"/>\n", points.join(" "));
+        if let (Some((_, first)), Some((_, last))) = (self.steps.first(), self.steps.last()) {
+            let r = margin as f64 / 2.0;
+            svg += &format!("  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"green\"/>\n", first.x, -first.y, r);
+            svg += &format!("  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"red\"/>\n", last.x, -last.y, r);
+        }
+        svg += "</svg>\n";
+
+        return svg;
+    }
+}
+
+pub struct RainRisk {
+    actions: Vec<Action>,
+}
+
+impl RainRisk {
+    pub fn load(filename: &str) -> Result<RainRisk, String> {
+        let mut actions: Vec<Action> = Vec::new();
 
         let file = File::open(filename).unwrap();
         let reader = BufReader::new(file);
 
-        for line in reader.lines() {
+        for (n, line) in reader.lines().enumerate() {
             let s = line.unwrap();
-            let cap = RE.captures(&s).unwrap();
+            if s.trim().is_empty() {
+                continue;
+            }
+
+            match Action::parse(&s) {
+                Ok(action) => actions.push(action),
+                Err(reason) => return Err(format!("line {}: {}", n+1, reason)),
+            }
+        }
+
+        return Ok(RainRisk { actions: actions });
+    }
+
+    // Final state after following all the actions with the given strategy.
+    pub fn navigate<S: Strategy>(&self, strategy: S) -> State {
+        return Navigator::new(strategy).run(&self.actions);
+    }
+
+    // Every state passed through while following the actions with the given strategy.
+    pub fn track<S: Strategy>(&self, strategy: S) -> Track {
+        let mut navigator = Navigator::new(strategy);
+        let mut steps = vec![(None, navigator.state)];
 
-            instructions.push( Instruction { op: cap[1].to_string(), value: cap[2].parse().unwrap()} );
+        for action in &self.actions {
+            navigator.step(action);
+            steps.push((Some(*action), navigator.state));
         }
 
-        return RainRisk { instructions: instructions }
+        return Track { steps: steps };
     }
 }
 
 impl super::Day for RainRisk {
     fn part1(&self) -> Result<i64, &str> {
-        let mut x: i32 = 0;
-        let mut y: i32 = 0;
-        let mut heading: i32 = 0;
-
-        for i in self.instructions.iter() {
-            // print!("{} {} -> ", i.op, i.value);
-            match i.op.as_str() {
-                "N" => {
-                    y += i.value as i32;
-                }
-                "S" => {
-                    y -= i.value as i32;
-                }
-                "E" => {
-                    x += i.value as i32;
-                }
-                "W" => {
-                    x -= i.value as i32;
-                }
-                "R" => {
-                    let turns: i32 = i.value / 90;
-                    heading -= turns;
-                    heading = heading.rem_euclid(4);
-                }
-                "L" => {
-                    let turns: i32 = i.value / 90;
-                    heading += turns;
-                    heading = heading.rem_euclid(4);
-                }
-                "F" => {
-                    match heading {
-                        0 => {
-                            // East
-                            x += i.value as i32;
-                        }
-                        1 => {
-                            // North
-                            y += i.value as i32;
-                        }
-                        2 => {
-                            // West
-                            x -= i.value as i32;
-                        }
-                        3 => {
-                            // South
-                            y -= i.value as i32;
-                        }
-                        _ => {
-                            panic!("Invalid heading: {}.", heading);
-                        }
-                    }
-                }
-                _ => {
-                    panic!("Invalid operation.");
-                }
-            }
-            // println!("{} {} {}", x, y, heading);
-        }
-
-        return Ok( (x.abs() + y.abs()) as i64 );
+        return Ok(self.navigate(ShipStrategy).manhattan());
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        let mut x: i32 = 0;
-        let mut y: i32 = 0;
-        let mut waypoint_x: i32 = 10;
-        let mut waypoint_y: i32 = 1;
-
-        for i in self.instructions.iter() {
-            // print!("{} {} -> ", i.op, i.value);
-            match i.op.as_str() {
-                "N" => {
-                    waypoint_y += i.value as i32;
-                }
-                "S" => {
-                    waypoint_y -= i.value as i32;
-                }
-                "E" => {
-                    waypoint_x += i.value as i32;
-                }
-                "W" => {
-                    waypoint_x -= i.value as i32;
-                }
-                "R" => {
-                    let turns: i32 = (-i.value / 90).rem_euclid(4);
-                    for _n in 0..turns {
-                        let new_y = waypoint_x;
-                        let new_x = -waypoint_y;
-                        waypoint_x = new_x;
-                        waypoint_y = new_y;
-                    }
-                }
-                "L" => {
-                    let turns: i32 = (i.value / 90).rem_euclid(4);
-                    for _n in 0..turns {
-                        let new_y = waypoint_x;
-                        let new_x = -waypoint_y;
-                        waypoint_x = new_x;
-                        waypoint_y = new_y;
-                    }
-                }
-                "F" => {
-                    // Move to waypoint i.value times.
-                    x += waypoint_x * i.value;
-                    y += waypoint_y * i.value;
-                }
-                _ => {
-                    panic!("Invalid operation.");
-                }
-            }
-            // println!("{} {} {}", x, y, heading);
-        }
-
-        return Ok( (x.abs() + y.abs()) as i64 );
+        return Ok(self.navigate(WaypointStrategy).manhattan());
     }
 }
 
@@ -161,31 +296,83 @@ mod tests {
 
     #[test]
     fn test_load() {
-        let rr = &RainRisk::load("data/day12_example1.txt");
-        assert_eq!(rr.instructions.len(), 5);
+        let rr = &RainRisk::load("data/day12_example1.txt").unwrap();
+        assert_eq!(rr.actions.len(), 5);
+        assert_eq!(rr.actions[3], Action::Right(1));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Action::parse("L270"), Ok(Action::Left(3)));
+        assert_eq!(Action::parse("R45"), Err(String::from("rotation of 45 degrees is not a right angle")));
+        assert_eq!(Action::parse("X10"), Err(String::from("unrecognised operation 'X'")));
+        assert_eq!(Action::parse("F"), Err(String::from("unrecognised instruction 'F'")));
+        assert_eq!(RainRisk::load("data/day12_bad.txt").err(),
+                   Some(String::from("line 3: rotation of 45 degrees is not a right angle")));
+    }
+
+    #[test]
+    fn test_final_state() {
+        let rr = &RainRisk::load("data/day12_example1.txt").unwrap();
+        let ship = rr.navigate(ShipStrategy);
+        assert_eq!((ship.x, ship.y, ship.heading), (17, -8, 3));
+
+        let waypoint = rr.navigate(WaypointStrategy);
+        assert_eq!((waypoint.x, waypoint.y), (214, -72));
+        assert_eq!((waypoint.waypoint_x, waypoint.waypoint_y), (4, -10));
+    }
+
+    #[test]
+    fn test_track() {
+        let rr = &RainRisk::load("data/day12_example1.txt").unwrap();
+        let track = rr.track(ShipStrategy);
+        assert_eq!(track.steps.len(), 6);
+        assert_eq!(track.steps[0], (None, State::new()));
+        assert_eq!(track.steps[5].1, rr.navigate(ShipStrategy));
+
+        let track = rr.track(WaypointStrategy);
+        assert_eq!(track.steps[1].0, Some(Action::Forward(10)));
+        assert_eq!((track.steps[1].1.x, track.steps[1].1.y), (100, 10));
+    }
+
+    #[test]
+    fn test_track_export() {
+        let rr = &RainRisk::load("data/day12_example1.txt").unwrap();
+        let csv = rr.track(ShipStrategy).to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "step,action,x,y,heading,waypoint_x,waypoint_y");
+        assert_eq!(lines[1], "0,,0,0,0,10,1");
+        assert_eq!(lines[4], "3,F7,17,3,0,10,1");
+        assert_eq!(lines[5], "4,R90,17,3,270,10,1");
+
+        let svg = rr.track(WaypointStrategy).to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("points=\"0,0 100,-10 100,-10 170,-38 170,-38 214,72\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
     }
 
     #[test]
     fn test_part1_ex1() {
-        let rr = &RainRisk::load("data/day12_example1.txt");
+        let rr = &RainRisk::load("data/day12_example1.txt").unwrap();
         assert_eq!(rr.part1(), Ok(25));
     }
 
     #[test]
     fn test_part1() {
-        let rr = &RainRisk::load("data/day12_input.txt");
+        let rr = &RainRisk::load("data/day12_input.txt").unwrap();
         assert_eq!(rr.part1(), Ok(998));
     }
 
     #[test]
     fn test_part2_ex1() {
-        let rr = &RainRisk::load("data/day12_example1.txt");
+        let rr = &RainRisk::load("data/day12_example1.txt").unwrap();
         assert_eq!(rr.part2(), Ok(286));
     }
 
     #[test]
     fn test_part2() {
-        let rr = &RainRisk::load("data/day12_input.txt");
+        let rr = &RainRisk::load("data/day12_input.txt").unwrap();
         assert_eq!(rr.part2(), Ok(71586));
     }
-}
\ No newline at end of file
+}
@@ -2,11 +2,42 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::collections::HashMap;
+
+// Numbers below this are tracked in a dense array, anything larger in a hash map.
+const DENSE_LIMIT: usize = 1 << 25;
 
 pub struct Recitation {
     initial: Vec<usize>,
 }
 
+// Round in which each number was last said (0 = never).
+// Every number said after the starting ones is an age, so it's smaller than the
+// round count and a dense array sized from the target round covers nearly all of
+// them.  Large starting numbers (or very long games) fall back to the hash map.
+struct LastSaid {
+    dense: Vec<u32>,
+    sparse: HashMap<usize, u32>,
+}
+
+impl LastSaid {
+    fn new(nth: usize) -> LastSaid {
+        LastSaid { dense: vec![0; nth.min(DENSE_LIMIT)], sparse: HashMap::new() }
+    }
+
+    // Record that n was said in this round, returning the round it was said before.
+    fn swap(&mut self, n: usize, round: usize) -> usize {
+        let previous = if n < self.dense.len() {
+            std::mem::replace(&mut self.dense[n], round as u32)
+        }
+        else {
+            self.sparse.insert(n, round as u32).unwrap_or(0)
+        };
+
+        return previous as usize;
+    }
+}
+
 impl Recitation {
     pub fn load(filename: &str) -> Recitation {
         lazy_static! {
@@ -27,39 +58,74 @@ impl Recitation {
         return Recitation { initial: numbers};
     }
 
+    // Iterate over the numbers said in the first `rounds` rounds of the game.
+    pub fn spoken(&self, rounds: usize) -> Spoken<'_> {
+        assert!(rounds <= u32::MAX as usize, "round numbers must fit in u32");
+
+        return Spoken {
+            initial: &self.initial,
+            limit: rounds,
+            round: 0,
+            say_next: 0,
+            round_last_said: LastSaid::new(rounds),
+        };
+    }
+
     fn nth_said(&self, nth: usize) -> usize{
-        let mut round_last_said: Vec<usize> = vec![0;100000000];
-        let mut round: usize = 0;
-        let mut say_next: usize = 0;
-
-        // Say the initial numbers
-        for n in &self.initial {
-            round += 1;
-            let last_round = round_last_said[*n];
-            if last_round == 0 {
-                say_next = 0;
-            }
-            else {
-                say_next = round - last_round;
-            }
-            round_last_said[*n] = round;
+        return self.spoken(nth).last().unwrap().number;
+    }
+
+    // First round (within the limit) in which n is said.
+    pub fn first_said(&self, n: usize, rounds: usize) -> Option<usize> {
+        return self.spoken(rounds).find(|s| s.number == n).map(|s| s.round);
+    }
+
+    // The turn within the limit with the longest gap since its number was last said.
+    pub fn largest_gap(&self, rounds: usize) -> Option<Turn> {
+        return self.spoken(rounds)
+            .filter(|s| s.age.is_some())
+            .max_by_key(|s| (s.age, std::cmp::Reverse(s.round)));
+    }
+}
+
+// One turn of the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Turn {
+    pub round: usize,        // 1-based
+    pub number: usize,       // number said this round
+    pub age: Option<usize>,  // rounds since it was said before, None if new
+}
+
+// Iterator over the turns of a game, created by Recitation::spoken().
+pub struct Spoken<'a> {
+    initial: &'a [usize],
+    limit: usize,
+    round: usize,
+    say_next: usize,
+    round_last_said: LastSaid,
+}
+
+impl<'a> Iterator for Spoken<'a> {
+    type Item = Turn;
+
+    fn next(&mut self) -> Option<Turn> {
+        if self.round >= self.limit {
+            return None;
         }
 
-        // Say remaining numbers up to limit
-        while round < nth-1 {
-            round += 1;
-            let say = say_next;
-            let last_round = round_last_said[say_next];
-            if last_round == 0 {
-                say_next = 0;
-            }
-            else {
-                say_next = round - last_round;
-            }
-            round_last_said[say] = round;
+        self.round += 1;
+        let number = if self.round <= self.initial.len() {
+            self.initial[self.round-1]
         }
+        else {
+            self.say_next
+        };
 
-        return say_next;
+        let last_round = self.round_last_said.swap(number, self.round);
+        let age = if last_round == 0 { None } else { Some(self.round - last_round) };
+        self.say_next = age.unwrap_or(0);
+
+        return Some(Turn { round: self.round, number: number, age: age });
     }
 }
 
@@ -87,6 +153,47 @@ mod tests {
         assert_eq!(rr.initial[2], 6);
     }
 
+    #[test]
+    fn test_last_said() {
+        let mut ls = LastSaid::new(4);
+        assert_eq!(ls.dense.len(), 4);
+        assert_eq!(ls.swap(2, 1), 0);
+        assert_eq!(ls.swap(2, 5), 1);
+        assert_eq!(ls.swap(1000, 6), 0);
+        assert_eq!(ls.swap(1000, 9), 6);
+        assert_eq!(ls.sparse.len(), 1);
+    }
+
+    #[test]
+    fn test_early_rounds() {
+        let rr = &Recitation::load("data/day15_example1.txt");
+        assert_eq!(rr.nth_said(1), 0);
+        assert_eq!(rr.nth_said(3), 6);
+        assert_eq!(rr.nth_said(4), 0);
+        assert_eq!(rr.nth_said(10), 0);
+        assert_eq!(rr.nth_said(9), 4);
+    }
+
+    #[test]
+    fn test_spoken() {
+        let rr = &Recitation::load("data/day15_example1.txt");
+        let said: Vec<usize> = rr.spoken(10).map(|s| s.number).collect();
+        assert_eq!(said, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+
+        let turns: Vec<Turn> = rr.spoken(6).skip(3).collect();
+        assert_eq!(turns[0], Turn { round: 4, number: 0, age: Some(3) });
+        assert_eq!(turns[2], Turn { round: 6, number: 3, age: Some(1) });
+        assert_eq!(rr.spoken(2020).count(), 2020);
+    }
+
+    #[test]
+    fn test_queries() {
+        let rr = &Recitation::load("data/day15_example1.txt");
+        assert_eq!(rr.first_said(4, 10), Some(9));
+        assert_eq!(rr.first_said(5, 9), None);
+        assert_eq!(rr.largest_gap(10), Some(Turn { round: 8, number: 0, age: Some(4) }));
+    }
+
     #[test]
     fn test_part1_examples() {
         let examples: [(&str, i64); 7] = [
@@ -1,6 +1,8 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use itertools::Itertools;
+
+// The sum the expense report entries need to add up to.
+const TARGET: i64 = 2020;
 
 pub struct ReportRepair {
     // a list of expense report values
@@ -28,29 +30,133 @@ impl ReportRepair {
 
         return day1;
     }
+
+    // Every set of k distinct entries summing to target, as ascending index tuples, sorted.
+    pub fn find_k_sum(&self, k: usize, target: i64) -> Vec<Vec<usize>> {
+        let mut found: Vec<Vec<usize>> = Vec::new();
+        self.search(k, target, false, &mut found);
+        found.sort();
+
+        return found;
+    }
+
+    // The first set of k entries found summing to target.
+    pub fn first_k_sum(&self, k: usize, target: i64) -> Option<Vec<usize>> {
+        let mut found: Vec<Vec<usize>> = Vec::new();
+        self.search(k, target, true, &mut found);
+
+        return found.pop();
+    }
+
+    fn search(&self, k: usize, target: i64, first_only: bool, found: &mut Vec<Vec<usize>>) {
+        if k == 0 || k > self.entries.len() {
+            return;
+        }
+
+        // Work on (value, index) pairs sorted by value.
+        let mut sorted: Vec<(i64, usize)> = self.entries.iter().cloned().zip(0..).collect();
+        sorted.sort();
+
+        let mut chosen: Vec<usize> = Vec::new();
+        k_sum(&sorted, k, target, &mut chosen, first_only, found);
+
+        for tuple in found.iter_mut() {
+            tuple.sort();
+        }
+    }
+
+    fn product(&self, indices: &[usize]) -> i64 {
+        return indices.iter().map(|n| self.entries[*n]).product();
+    }
 }
 
-impl super::Day for ReportRepair {
-    fn part1(&self) -> Result<i64, &str> {
-        for n in 0..self.entries.len() - 1 {
-            for m in n + 1..self.entries.len() {
-                let sum = self.entries[n] + self.entries[m];
-                if sum == 2020 {
-                    return Ok(self.entries[n] * self.entries[m]);
+// Find k entries of sorted (a slice of (value, index) in value order) summing to target.
+// Indices already chosen are in `chosen`.  Fixing one entry at a time and finishing
+// with a two-pointer sweep makes this O(n^(k-1)).  Returns true once done
+// (a result was found and only the first was wanted).
+fn k_sum(sorted: &[(i64, usize)], k: usize, target: i64, chosen: &mut Vec<usize>,
+         first_only: bool, found: &mut Vec<Vec<usize>>) -> bool {
+    let record = |indices: &[usize], found: &mut Vec<Vec<usize>>| {
+        let mut tuple = chosen.clone();
+        tuple.extend_from_slice(indices);
+        found.push(tuple);
+        return first_only;
+    };
+
+    if k == 1 {
+        for (value, index) in sorted {
+            if *value == target && record(&[*index], found) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    if k == 2 {
+        let (mut lo, mut hi) = (0, sorted.len());
+        while lo + 1 < hi {
+            let sum = sorted[lo].0 + sorted[hi-1].0;
+            if sum < target {
+                lo += 1;
+            }
+            else if sum > target {
+                hi -= 1;
+            }
+            else if sorted[lo].0 == sorted[hi-1].0 {
+                // Everything from lo to hi is the same value: every pair of them works.
+                for a in lo..hi {
+                    for b in a+1..hi {
+                        if record(&[sorted[a].1, sorted[b].1], found) {
+                            return true;
+                        }
+                    }
                 }
+                return false;
             }
+            else {
+                // Pair every copy of the low value with every copy of the high value.
+                let lo_end = lo + sorted[lo..].iter().take_while(|e| e.0 == sorted[lo].0).count();
+                let hi_start = hi - sorted[..hi].iter().rev().take_while(|e| e.0 == sorted[hi-1].0).count();
+                for a in lo..lo_end {
+                    for b in hi_start..hi {
+                        if record(&[sorted[a].1, sorted[b].1], found) {
+                            return true;
+                        }
+                    }
+                }
+                lo = lo_end;
+                hi = hi_start;
+            }
+        }
+        return false;
+    }
+
+    // Fix each entry in turn as the smallest of the tuple and solve for k-1 above it.
+    for n in 0..sorted.len() + 1 - k {
+        chosen.push(sorted[n].1);
+        let done = k_sum(&sorted[n+1..], k-1, target - sorted[n].0, chosen, first_only, found);
+        chosen.pop();
+        if done {
+            return true;
+        }
+    }
+
+    return false;
+}
+
+impl super::Day for ReportRepair {
+    fn part1(&self) -> Result<i64, &str> {
+        match self.first_k_sum(2, TARGET) {
+            Some(indices) => return Ok(self.product(&indices)),
+            None => return Err("No result found"),
         }
-        return Err("No result found");
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        for values in self.entries.iter().combinations(3) {
-            let sum = values.iter().fold(0, |acc, &x| acc + x);
-            if sum == 2020 {
-                return Ok(values.iter().fold(1, |acc, &x| acc * x));
-            }
+        match self.first_k_sum(3, TARGET) {
+            Some(indices) => return Ok(self.product(&indices)),
+            None => return Err("No result found"),
         }
-        return Err("No result found");
     }
 }
 
@@ -59,7 +165,42 @@ mod tests {
     use super::*;
     use crate::Day;
 
-        #[test]
+    #[test]
+    fn test_examples() {
+        let rr = &ReportRepair::load("data/day1_example1.txt");
+
+        assert_eq!(rr.part1(), Ok(514579));
+        assert_eq!(rr.part2(), Ok(241861950));
+    }
+
+    #[test]
+    fn test_find_k_sum() {
+        let rr = &ReportRepair::load("data/day1_example1.txt");
+
+        assert_eq!(rr.find_k_sum(2, 2020), vec![vec![0, 3]]);
+        assert_eq!(rr.find_k_sum(3, 2020), vec![vec![1, 2, 4]]);
+        assert_eq!(rr.find_k_sum(1, 299), vec![vec![3]]);
+        assert_eq!(rr.find_k_sum(2, 5), Vec::<Vec<usize>>::new());
+        assert_eq!(rr.find_k_sum(7, 2020), Vec::<Vec<usize>>::new());
+        assert_eq!(rr.first_k_sum(2, 2020), Some(vec![0, 3]));
+        assert_eq!(rr.first_k_sum(2, 1), None);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let rr = &ReportRepair { entries: vec![1010, 5, 1010, 1010, 2015, 5] };
+
+        // Each 1010 can pair with each other one, but not with itself.
+        assert_eq!(rr.find_k_sum(2, 2020), vec![vec![0, 2], vec![0, 3], vec![1, 4], vec![2, 3], vec![4, 5]]);
+        assert_eq!(rr.find_k_sum(3, 3030),
+                   vec![vec![0, 1, 4], vec![0, 2, 3], vec![0, 4, 5], vec![1, 2, 4],
+                        vec![1, 3, 4], vec![2, 4, 5], vec![3, 4, 5]]);
+        assert_eq!(rr.find_k_sum(3, 2025),
+                   vec![vec![0, 1, 2], vec![0, 1, 3], vec![0, 2, 5], vec![0, 3, 5],
+                        vec![1, 2, 3], vec![1, 4, 5], vec![2, 3, 5]]);
+    }
+
+    #[test]
     fn test_part1() {
         let rr = &ReportRepair::load("data/day1_input.txt");
 
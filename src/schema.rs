@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::fs;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+// The passport rules, as shipped in data/passport_schema.toml.
+const PASSPORT_SCHEMA: &str = include_str!("../data/passport_schema.toml");
+
+// How a field's value is checked.
+#[derive(Debug, Clone)]
+pub enum Check {
+    Any,
+    Year { min: u32, max: u32 },          // four digits, within range
+    Number { units: Vec<(String, u32, u32)> },  // digits followed by a unit, range per unit
+    Pattern(Regex),
+    OneOf(Vec<String>),
+}
+
+impl Check {
+    // Ok if the value passes, otherwise the reason it doesn't.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        lazy_static! {
+            static ref YEAR_RE: Regex = Regex::new("^([0-9]{4})$").unwrap();
+            static ref NUMBER_RE: Regex = Regex::new("^([0-9]+)(.*)$").unwrap();
+        }
+
+        match self {
+            Check::Any => {
+                return Ok(());
+            }
+            Check::Year { min, max } => {
+                if !YEAR_RE.is_match(value) {
+                    return Err(format!("{} is not a four digit year", value));
+                }
+                let year: u32 = value.parse().unwrap();
+                if year < *min || year > *max {
+                    return Err(format!("{} out of range {}-{}", year, min, max));
+                }
+                return Ok(());
+            }
+            Check::Number { units } => {
+                let cap = match NUMBER_RE.captures(value) {
+                    Some(cap) => cap,
+                    None => return Err(format!("{} is not a number", value)),
+                };
+                let number: u32 = match cap[1].parse() {
+                    Ok(number) => number,
+                    Err(_) => return Err(format!("{} is too large", &cap[1])),
+                };
+                for (unit, min, max) in units {
+                    if &cap[2] == unit {
+                        if number < *min || number > *max {
+                            return Err(format!("{} with unit '{}' out of range {}-{}", number, unit, min, max));
+                        }
+                        return Ok(());
+                    }
+                }
+                return Err(format!("{} has unknown unit '{}'", number, &cap[2]));
+            }
+            Check::Pattern(re) => {
+                if !re.is_match(value) {
+                    return Err(format!("{} does not match {}", value, re.as_str()));
+                }
+                return Ok(());
+            }
+            Check::OneOf(values) => {
+                if !values.iter().any(|v| v == value) {
+                    return Err(format!("{} is not one of {}", value, values.join(", ")));
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldRule {
+    pub name: String,
+    pub required: bool,
+    pub check: Check,
+}
+
+// The set of fields a document may have, and how each is validated.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub fields: Vec<FieldRule>,
+}
+
+// A value on the right of `key = value` in a schema file.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Schema {
+    // The built-in passport schema.
+    pub fn passport() -> Schema {
+        return Schema::parse(PASSPORT_SCHEMA).unwrap();
+    }
+
+    pub fn load(filename: &str) -> Result<Schema, String> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        return Schema::parse(&text);
+    }
+
+    // Parse a schema written as a small subset of TOML: one [section] per field,
+    // holding `key = value` lines with string, integer, boolean or string list values.
+    pub fn parse(text: &str) -> Result<Schema, String> {
+        lazy_static! {
+            static ref SECTION_RE: Regex = Regex::new(r"^\[([A-Za-z0-9_-]+)\]$").unwrap();
+            static ref KEY_RE: Regex = Regex::new(r"^([A-Za-z0-9_.-]+)\s*=\s*(.+)$").unwrap();
+        }
+
+        // Gather the key/value pairs of each section, in order.
+        let mut sections: Vec<(String, HashMap<String, Value>)> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(cap) = SECTION_RE.captures(line) {
+                sections.push((cap[1].to_string(), HashMap::new()));
+            }
+            else if let Some(cap) = KEY_RE.captures(line) {
+                let value = parse_value(&cap[2]).map_err(|e| format!("line {}: {}", n+1, e))?;
+                match sections.last_mut() {
+                    Some((_, keys)) => {
+                        keys.insert(cap[1].to_string(), value);
+                    }
+                    None => return Err(format!("line {}: key outside of a [field] section", n+1)),
+                }
+            }
+            else {
+                return Err(format!("line {}: unrecognised line '{}'", n+1, line));
+            }
+        }
+
+        let mut fields: Vec<FieldRule> = Vec::new();
+        for (name, keys) in sections {
+            fields.push(field_rule(&name, &keys).map_err(|e| format!("[{}]: {}", name, e))?);
+        }
+
+        return Ok(Schema { fields: fields });
+    }
+
+    // Names of required fields the document doesn't have.
+    pub fn missing<'a>(&'a self, doc: &HashMap<String, String>) -> Vec<&'a str> {
+        let mut missing: Vec<&str> = Vec::new();
+        for field in &self.fields {
+            if field.required && !doc.contains_key(&field.name) {
+                missing.push(&field.name);
+            }
+        }
+
+        return missing;
+    }
+
+    // The fields present in the document that fail their check, with the reasons.
+    pub fn failures<'a>(&'a self, doc: &HashMap<String, String>) -> Vec<(&'a str, String)> {
+        let mut failures: Vec<(&str, String)> = Vec::new();
+        for field in &self.fields {
+            if let Some(value) = doc.get(&field.name) {
+                if let Err(reason) = field.check.check(value) {
+                    failures.push((&field.name, reason));
+                }
+            }
+        }
+
+        return failures;
+    }
+
+    pub fn is_complete(&self, doc: &HashMap<String, String>) -> bool {
+        return self.missing(doc).is_empty();
+    }
+
+    pub fn is_valid(&self, doc: &HashMap<String, String>) -> bool {
+        return self.is_complete(doc) && self.failures(doc).is_empty();
+    }
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    let s = s.trim();
+
+    if s == "true" || s == "false" {
+        return Ok(Value::Bool(s == "true"));
+    }
+    if let Ok(n) = s.parse::<i64>() {
+        return Ok(Value::Int(n));
+    }
+    if s.starts_with('[') && s.ends_with(']') {
+        let mut items: Vec<String> = Vec::new();
+        for item in split_list(&s[1..s.len()-1])? {
+            if item.trim().is_empty() {
+                continue;
+            }
+            match parse_value(item)? {
+                Value::Str(item) => items.push(item),
+                _ => return Err(format!("list items must be strings: {}", s)),
+            }
+        }
+        return Ok(Value::List(items));
+    }
+    if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') {
+        // Literal string, no escapes.
+        return Ok(Value::Str(s[1..s.len()-1].to_string()));
+    }
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        let mut value = String::new();
+        let mut chars = s[1..s.len()-1].chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some(c) => value.push(c),
+                    None => return Err(format!("dangling escape in {}", s)),
+                }
+            }
+            else {
+                value.push(c);
+            }
+        }
+        return Ok(Value::Str(value));
+    }
+
+    return Err(format!("unrecognised value {}", s));
+}
+
+// Split list contents on the commas between items, leaving commas inside quotes alone.
+fn split_list(s: &str) -> Result<Vec<&str>, String> {
+    let mut items: Vec<&str> = Vec::new();
+    let mut start = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                }
+                else if c == '\\' && q == '"' {
+                    escaped = true;
+                }
+                else if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                else if c == ',' {
+                    items.push(&s[start..i]);
+                    start = i + 1;
+                }
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("unterminated string in [{}]", s));
+    }
+    items.push(&s[start..]);
+
+    return Ok(items);
+}
+
+// Parse a "min-max" range.
+fn parse_range(s: &str) -> Result<(u32, u32), String> {
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() == 2 {
+        if let (Ok(min), Ok(max)) = (parts[0].trim().parse(), parts[1].trim().parse()) {
+            return Ok((min, max));
+        }
+    }
+    return Err(format!("invalid range '{}', expected min-max", s));
+}
+
+fn field_rule(name: &str, keys: &HashMap<String, Value>) -> Result<FieldRule, String> {
+    let required = match keys.get("required") {
+        Some(Value::Bool(b)) => *b,
+        None => false,
+        _ => return Err(String::from("required must be true or false")),
+    };
+
+    let get_int = |key: &str| -> Result<u32, String> {
+        match keys.get(key) {
+            Some(Value::Int(n)) if *n >= 0 => Ok(*n as u32),
+            _ => Err(format!("{} must be a non-negative integer", key)),
+        }
+    };
+    let get_str = |key: &str| -> Result<String, String> {
+        match keys.get(key) {
+            Some(Value::Str(s)) => Ok(s.clone()),
+            _ => Err(format!("{} must be a string", key)),
+        }
+    };
+
+    let kind = match keys.get("type") {
+        None => String::from("any"),
+        Some(_) => get_str("type")?,
+    };
+    let check = match kind.as_str() {
+        "any" => Check::Any,
+        "year" => Check::Year { min: get_int("min")?, max: get_int("max")? },
+        "number" => {
+            let mut units: Vec<(String, u32, u32)> = Vec::new();
+            if keys.contains_key("range") {
+                // Unitless number.
+                let (min, max) = parse_range(&get_str("range")?)?;
+                units.push((String::new(), min, max));
+            }
+            for key in keys.keys() {
+                if let Some(unit) = key.strip_prefix("unit.") {
+                    let (min, max) = parse_range(&get_str(key)?)?;
+                    units.push((unit.to_string(), min, max));
+                }
+            }
+            if units.is_empty() {
+                return Err(String::from("number needs a range or at least one unit.<name>"));
+            }
+            units.sort();
+            Check::Number { units: units }
+        }
+        "regex" => {
+            let pattern = get_str("pattern")?;
+            Check::Pattern(Regex::new(&pattern).map_err(|e| e.to_string())?)
+        }
+        "enum" => {
+            match keys.get("values") {
+                Some(Value::List(values)) => Check::OneOf(values.clone()),
+                _ => return Err(String::from("values must be a list of strings")),
+            }
+        }
+        _ => return Err(format!("unknown type '{}'", kind)),
+    };
+
+    // Catch misspelt keys rather than silently ignoring them.
+    for key in keys.keys() {
+        let known = match (kind.as_str(), key.as_str()) {
+            (_, "required") | (_, "type") => true,
+            ("year", "min") | ("year", "max") => true,
+            ("number", "range") => true,
+            ("number", k) => k.starts_with("unit."),
+            ("regex", "pattern") => true,
+            ("enum", "values") => true,
+            _ => false,
+        };
+        if !known {
+            return Err(format!("unknown key '{}' for type {}", key, kind));
+        }
+    }
+
+    return Ok(FieldRule { name: name.to_string(), required: required, check: check });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        return fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    }
+
+    #[test]
+    fn test_passport_schema() {
+        let schema = Schema::passport();
+        assert_eq!(schema.fields.len(), 8);
+        assert_eq!(schema.fields.iter().filter(|f| f.required).count(), 7);
+    }
+
+    #[test]
+    fn test_checks() {
+        let schema = Schema::passport();
+        let check = |name: &str, value: &str| {
+            schema.fields.iter().find(|f| f.name == name).unwrap().check.check(value)
+        };
+
+        assert_eq!(check("byr", "2002"), Ok(()));
+        assert_eq!(check("byr", "2003"), Err(String::from("2003 out of range 1920-2002")));
+        assert_eq!(check("hgt", "60in"), Ok(()));
+        assert_eq!(check("hgt", "190in"), Err(String::from("190 with unit 'in' out of range 59-76")));
+        assert_eq!(check("hgt", "190"), Err(String::from("190 has unknown unit ''")));
+        assert_eq!(check("hcl", "#123abc"), Ok(()));
+        assert!(check("hcl", "123abc").is_err());
+        assert_eq!(check("ecl", "wat"), Err(String::from("wat is not one of amb, blu, brn, gry, grn, hzl, oth")));
+        assert_eq!(check("pid", "000000001"), Ok(()));
+        assert!(check("pid", "0123456789").is_err());
+    }
+
+    #[test]
+    fn test_custom_schema() {
+        let text = "[name]\nrequired = true\n\n[age]\ntype = \"number\"\nrange = \"0-150\"\n";
+        let schema = Schema::parse(text).unwrap();
+        assert!(schema.is_valid(&doc(&[("name", "Bob"), ("age", "42")])));
+        assert!(schema.is_valid(&doc(&[("name", "Bob")])));
+        assert!(!schema.is_valid(&doc(&[("name", "Bob"), ("age", "200")])));
+        assert_eq!(schema.missing(&doc(&[("age", "1")])), vec!["name"]);
+    }
+
+    #[test]
+    fn test_list_values() {
+        assert_eq!(parse_value(r#"["a,b", 'c,', "d\"e,f", ]"#),
+                   Ok(Value::List(vec![String::from("a,b"), String::from("c,"), String::from("d\"e,f")])));
+
+        let schema = Schema::parse("[x]\ntype = \"enum\"\nvalues = [\"a,b\", \"c\"]\n").unwrap();
+        assert!(schema.is_valid(&doc(&[("x", "a,b")])));
+        assert!(!schema.is_valid(&doc(&[("x", "a")])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Schema::parse("required = true\n").is_err());
+        assert!(Schema::parse("[x]\ntype = \"colour\"\n").is_err());
+        assert!(Schema::parse("[x]\ntype = \"year\"\nmin = 1\n").is_err());
+        assert!(Schema::parse("[x]\nwhat\n").is_err());
+        assert_eq!(Schema::parse("[x]\nrequried = true\n").err(),
+                   Some(String::from("[x]: unknown key 'requried' for type any")));
+        assert_eq!(Schema::parse("[x]\ntype = \"year\"\nmin = 1\nmax = 2\npattern = 'x'\n").err(),
+                   Some(String::from("[x]: unknown key 'pattern' for type year")));
+        assert!(Schema::parse("[x]\ntype = \"enum\"\nvalues = [\"a, \"b\"]\n").is_err());
+    }
+}
@@ -1,9 +1,85 @@
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
 use array2d::Array2D;
 use std::cmp::max;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Which seats count as neighbors of a seat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighborhood {
+    Adjacent,              // nearest seat in each of the 8 directions, if immediately adjacent
+    LineOfSight(i32),      // first seat seen in each direction, up to the given distance
+    Wrapping(i32),         // line of sight, wrapping around the edges of the layout
+}
+
+// A seating policy: how many occupied neighbors let an empty seat become occupied
+// (birth) or an occupied seat stay occupied (survival).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+    neighborhood: Neighborhood,
+}
+
+impl Rule {
+    // Neighbor counts run from 0 to 8.
+    pub fn new(birth: &[usize], survival: &[usize], neighborhood: Neighborhood) -> Result<Rule, String> {
+        let mut rule = Rule { birth: [false; 9], survival: [false; 9], neighborhood: neighborhood };
+        for (counts, table) in [(birth, &mut rule.birth), (survival, &mut rule.survival)] {
+            for n in counts {
+                if *n > 8 {
+                    return Err(format!("invalid neighbor count {}", n));
+                }
+                table[*n] = true;
+            }
+        }
+
+        return Ok(rule);
+    }
+
+    // Parse birth/survival counts in "B0/S0123" notation.
+    pub fn parse(s: &str, neighborhood: Neighborhood) -> Result<Rule, String> {
+        let mut birth: Vec<usize> = Vec::new();
+        let mut survival: Vec<usize> = Vec::new();
+
+        for part in s.trim().split('/') {
+            let counts = match part.chars().next() {
+                Some('B') => &mut birth,
+                Some('S') => &mut survival,
+                _ => return Err(format!("expected B or S in rule '{}'", s)),
+            };
+            for c in part.chars().skip(1) {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => counts.push(n as usize),
+                    _ => return Err(format!("invalid neighbor count '{}' in rule '{}'", c, s)),
+                }
+            }
+        }
+
+        return Rule::new(&birth, &survival, neighborhood);
+    }
+
+    // The puzzle's rule: empty seats with no occupied neighbors fill, occupied
+    // seats empty once `thresh` or more neighbors are occupied.
+    pub fn puzzle(thresh: usize, neighborhood: Neighborhood) -> Rule {
+        let survival: Vec<usize> = (0..thresh.min(9)).collect();
+        return Rule::new(&[0], &survival, neighborhood).unwrap();
+    }
+
+    fn next(&self, occupied: bool, neighbors: i32) -> bool {
+        let n = neighbors as usize;
+        if occupied {
+            return n < 9 && self.survival[n];
+        }
+        return n < 9 && self.birth[n];
+    }
+}
 
 pub struct SeatingSystem {
     seats: HashMap<(i32, i32), bool>,   // Every entry in seats represents a seat present.
@@ -17,8 +93,7 @@ impl SeatingSystem {
 
         let file = File::open(filename).unwrap();
         let reader = BufReader::new(file);
-        let mut max_x = 0;
-        let mut max_y = 0;
+        let mut width = 0;
 
         let mut y = 0;
         for line in reader.lines() {
@@ -27,58 +102,82 @@ impl SeatingSystem {
                 if c == 'L' {
                     // There's a seat here, store its location.
                     seats.insert((x, y), true);
-                    if x > max_x { max_x = x; }
-                    if y > max_y { max_y = y; }
                 }
                 x += 1;
             }
+            // Size the room by its lines, floor included, so wrapping happens at the real edges.
+            if x > width { width = x; }
             y += 1;
         }
 
-        return SeatingSystem { dim_x: max_x+1, dim_y: max_y+1, seats: seats };
+        return SeatingSystem { dim_x: width, dim_y: y, seats: seats };
     }
 
-    fn occupied_neighbors(&self, occupied: &Array2D<bool>, neighbors: &Vec<(i32, i32)>) -> i32 {
-        let mut count = 0;
-
-        for neighbor in neighbors {
-            if occupied[(neighbor.0 as usize, neighbor.1 as usize)] {
-                count += 1;
-            }
-        }
-
-        return count;
-    }
-
-    #[allow(dead_code)]
-    fn show_seating(&self, seating: &Array2D<bool>) {
+    // Draw a frame as text: '#' occupied, 'L' empty seat, '.' floor.
+    pub fn render_text(&self, seating: &Array2D<bool>) -> String {
+        let mut text = String::new();
         for y in 0..self.dim_y {
             for x in 0..self.dim_x {
                 let coord = (x as usize, y as usize);
                 if seating[coord] {
-                    print!("#");
+                    text.push('#');
+                }
+                else if self.seats.contains_key(&(x, y)) {
+                    text.push('L');
                 }
                 else {
-                    if self.seats.contains_key(&(x, y)) {
-                        print!("L");
-                    }
-                    else {
-                        print!(".");
-                    }
+                    text.push('.');
                 }
             }
-            println!();
+            text.push('\n');
         }
+
+        return text;
+    }
+
+    // Draw a frame as a binary PPM image, each cell `scale` pixels square.
+    pub fn render_ppm(&self, seating: &Array2D<bool>, scale: usize) -> Vec<u8> {
+        const OCCUPIED: [u8; 3] = [200, 40, 40];
+        const EMPTY: [u8; 3] = [230, 230, 230];
+        const FLOOR: [u8; 3] = [40, 40, 40];
+
+        let width = self.dim_x as usize * scale;
+        let height = self.dim_y as usize * scale;
+        let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+
+        for py in 0..height {
+            for px in 0..width {
+                let (x, y) = (px / scale, py / scale);
+                let color = if seating[(x, y)] {
+                    OCCUPIED
+                }
+                else if self.seats.contains_key(&(x as i32, y as i32)) {
+                    EMPTY
+                }
+                else {
+                    FLOOR
+                };
+                ppm.extend_from_slice(&color);
+            }
+        }
+
+        return ppm;
     }
 
     // Compute a HashMap that maps seats to a vector of neighboring seats.
-    fn compute_neighbors(&self, dist: i32) -> HashMap<(i32, i32), Vec<(i32, i32)>> {
+    fn compute_neighbors(&self, neighborhood: Neighborhood) -> HashMap<(i32, i32), Vec<(i32, i32)>> {
         let directions: [(i32, i32); 8] = [
             (-1, -1), (0, -1), (1, -1),
             (-1,  0),          (1,  0),
             (-1,  1), (0,  1), (1,  1)
         ];
 
+        let (dist, wrap) = match neighborhood {
+            Neighborhood::Adjacent => (1, false),
+            Neighborhood::LineOfSight(dist) => (dist, false),
+            Neighborhood::Wrapping(dist) => (dist, true),
+        };
+
         let mut neighbors: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
 
         for location in self.seats.keys() {
@@ -87,12 +186,23 @@ impl SeatingSystem {
             for dir in directions.iter() {
                 for distance in 1..dist+1 {
                     // println!("Dist: {}", distance);
-                    let x = location.0 as i32 + distance*dir.0;
-                    let y = location.1 as i32 + distance*dir.1;
+                    let mut x = location.0 as i32 + distance*dir.0;
+                    let mut y = location.1 as i32 + distance*dir.1;
+                    if wrap {
+                        x = x.rem_euclid(self.dim_x);
+                        y = y.rem_euclid(self.dim_y);
+                        if (x, y) == *location {
+                            // Came all the way around without seeing another seat.
+                            break;
+                        }
+                    }
 
                     if self.seats.contains_key(&(x, y)) {
-                        // We found the neighboring seat in this direction.
-                        seat_neighbors.push((x, y));
+                        // We found the neighboring seat in this direction.  On a small
+                        // wrapping grid another direction may already have found it.
+                        if !seat_neighbors.contains(&(x, y)) {
+                            seat_neighbors.push((x, y));
+                        }
                         break;
                     }
                 }
@@ -104,89 +214,235 @@ impl SeatingSystem {
         return neighbors;
     }
 
-    fn final_occupied(&self, thresh: i32, dist: i32) -> i32 {
-        let mut changed = true;
-        let mut a: Array2D<bool> = Array2D::filled_with(false, self.dim_x as usize, self.dim_y as usize);
-        let mut b: Array2D<bool> = Array2D::filled_with(false, self.dim_x as usize, self.dim_y as usize);
-        let mut a_before = true;
+    // Run the rule until the seating stops changing or starts repeating itself.
+    // With threads > 1, large rounds are evaluated in parallel.
+    pub fn simulate(&self, rule: &Rule, threads: usize) -> Outcome {
+        return self.run(rule, threads, false).0;
+    }
+
+    // As simulate(), but also capture the seating at every round, starting with the empty room.
+    pub fn record(&self, rule: &Rule, threads: usize) -> (Outcome, Recording<'_>) {
+        let (outcome, frames) = self.run(rule, threads, true);
+        return (outcome, Recording { layout: self, frames: frames });
+    }
+
+    fn run(&self, rule: &Rule, threads: usize, capture: bool) -> (Outcome, Vec<Array2D<bool>>) {
+        let mut sim = Simulation::new(self, rule);
+        let mut changes: Vec<usize> = Vec::new();
+        let mut frames: Vec<Array2D<bool>> = Vec::new();
+        let mut cycle: Option<Cycle> = None;
+
+        // Rounds at which each seating arrangement's hash was seen.  Only the hash is kept,
+        // so a match is confirmed by replaying the earlier round.
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        seen.insert(state_hash(&sim.occupied), vec![0]);
+        if capture {
+            frames.push(sim.grid(self));
+        }
 
-        let mut num_occupied = 0;
+        loop {
+            let changed = sim.step(threads);
+            if changed == 0 {
+                break;
+            }
+            changes.push(changed);
+            if capture {
+                frames.push(sim.grid(self));
+            }
 
-        // println!("Part 1");
+            let round = changes.len();
+            let rounds = seen.entry(state_hash(&sim.occupied)).or_default();
+            if let Some(previous) = rounds.iter().find(|r| self.replay(rule, **r) == sim.occupied) {
+                cycle = Some(Cycle { first_repeat: round, period: round - previous });
+                break;
+            }
+            rounds.push(round);
+        }
 
-        // Compute a map of which seats are neighbors of others.
-        let neighbors: HashMap<(i32, i32), Vec<(i32, i32)>> = self.compute_neighbors(dist);
+        let outcome = Outcome { occupied: sim.num_occupied, rounds: changes.len(), changes: changes, cycle: cycle };
+        return (outcome, frames);
+    }
 
-        while changed {
-            changed = false;
+    // Seating after the given number of rounds.
+    fn replay(&self, rule: &Rule, rounds: usize) -> Vec<bool> {
+        let mut sim = Simulation::new(self, rule);
+        for _ in 0..rounds {
+            sim.step(1);
+        }
+        return sim.occupied;
+    }
 
-            // Swap before and after arrays
-            a_before = !a_before;
+    // Run the rule until the seating stops changing, returning the number occupied.
+    pub fn final_occupied(&self, rule: &Rule) -> i32 {
+        return self.simulate(rule, 1).occupied;
+    }
+}
 
-            let before: &mut Array2D<bool>;
-            let after: &mut Array2D<bool>;
-            if a_before {
-                before = &mut a;
-                after = &mut b;
-            }
-            else {
-                before = &mut b;
-                after = &mut a;
+fn state_hash(occupied: &[bool]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    occupied.hash(&mut hasher);
+    return hasher.finish();
+}
+
+// A seating arrangement that came back around instead of settling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cycle {
+    pub first_repeat: usize,  // first round whose seating matches an earlier round
+    pub period: usize,        // how many rounds back that earlier round was
+}
+
+// Result of running a simulation until it's stable or cycling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub occupied: i32,        // seats occupied in the last round
+    pub rounds: usize,        // rounds in which something changed
+    pub changes: Vec<usize>,  // number of seats that changed in each of those rounds
+    pub cycle: Option<Cycle>, // None if the seating became stable
+}
+
+// Seating captured at each round of a simulation.
+pub struct Recording<'a> {
+    layout: &'a SeatingSystem,
+    pub frames: Vec<Array2D<bool>>,
+}
+
+impl<'a> Recording<'a> {
+    // Write each frame to dir/frame_NNNN.txt
+    pub fn write_text(&self, dir: &Path) -> io::Result<()> {
+        for (n, frame) in self.frames.iter().enumerate() {
+            fs::write(dir.join(format!("frame_{:04}.txt", n)), self.layout.render_text(frame))?;
+        }
+        return Ok(());
+    }
+
+    // Write each frame to dir/frame_NNNN.ppm
+    pub fn write_ppm(&self, dir: &Path, scale: usize) -> io::Result<()> {
+        for (n, frame) in self.frames.iter().enumerate() {
+            fs::write(dir.join(format!("frame_{:04}.ppm", n)), self.layout.render_ppm(frame, scale))?;
+        }
+        return Ok(());
+    }
+}
+
+// Running state of a seating simulation.  Seats are numbered by their index in `seats`.
+// Only seats in the frontier (those with a neighbor that changed last round, or
+// that changed themselves) are re-evaluated each round.
+struct Simulation<'a> {
+    rule: &'a Rule,
+    seats: Vec<(i32, i32)>,
+    neighbors: Vec<Vec<usize>>,   // seat -> seats it looks at
+    watchers: Vec<Vec<usize>>,    // seat -> seats that look at it
+    occupied: Vec<bool>,
+    num_occupied: i32,
+    frontier: Vec<usize>,
+    in_frontier: Vec<bool>,
+}
+
+// Rounds smaller than this per thread aren't worth spawning threads for.
+const MIN_SEATS_PER_THREAD: usize = 256;
+
+impl<'a> Simulation<'a> {
+    fn new(layout: &SeatingSystem, rule: &'a Rule) -> Simulation<'a> {
+        let mut seats: Vec<(i32, i32)> = layout.seats.keys().cloned().collect();
+        seats.sort();
+        let index: HashMap<(i32, i32), usize> = seats.iter().enumerate().map(|(n, s)| (*s, n)).collect();
+
+        let seat_neighbors = layout.compute_neighbors(rule.neighborhood);
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); seats.len()];
+        let mut watchers: Vec<Vec<usize>> = vec![Vec::new(); seats.len()];
+        for (n, seat) in seats.iter().enumerate() {
+            for neighbor in &seat_neighbors[seat] {
+                neighbors[n].push(index[neighbor]);
+                watchers[index[neighbor]].push(n);
             }
+        }
+
+        let n = seats.len();
+        Simulation {
+            rule: rule,
+            seats: seats,
+            neighbors: neighbors,
+            watchers: watchers,
+            occupied: vec![false; n],
+            num_occupied: 0,
+            frontier: (0..n).collect(),
+            in_frontier: vec![true; n],
+        }
+    }
 
-            num_occupied = 0;
+    // Current occupancy laid out on the floor plan.
+    fn grid(&self, layout: &SeatingSystem) -> Array2D<bool> {
+        let mut grid = Array2D::filled_with(false, layout.dim_x as usize, layout.dim_y as usize);
+        for (n, (x, y)) in self.seats.iter().enumerate() {
+            grid[(*x as usize, *y as usize)] = self.occupied[n];
+        }
 
-            // For each chair...
-            for (x, y) in self.seats.keys() {
-                let coord = (*x as usize, *y as usize);
+        return grid;
+    }
 
-                // count occupied neighbors of this chair.
-                // println!("Evaluating seat {} {}", x, y);
-                let num_neighbors = self.occupied_neighbors(&before, &neighbors[&(*x, *y)]);
+    // Which of these seats change state this round.
+    fn changed_in(&self, seats: &[usize]) -> Vec<usize> {
+        let mut changed: Vec<usize> = Vec::new();
 
-                // Apply the rules to update occupancy.
-                if num_neighbors == 0 {
-                    // seat becomes occupied
-                    after[coord] = true;
-                }
-                else if num_neighbors >= thresh {
-                    // seat becomes unoccupied
-                    after[coord] = false;
-                }
-                else {
-                    // seat stays the same
-                    after[coord] = before[coord]
+        for seat in seats {
+            let mut count = 0;
+            for neighbor in &self.neighbors[*seat] {
+                if self.occupied[*neighbor] {
+                    count += 1;
                 }
+            }
+            if self.rule.next(self.occupied[*seat], count) != self.occupied[*seat] {
+                changed.push(*seat);
+            }
+        }
 
-                // Count total seats occupied in after state.
-                if after[coord] {
-                    num_occupied += 1;
-                }
+        return changed;
+    }
 
-                // flag any changes
-                if before[coord] != after[coord] {
-                    // println!("Change detected at seat {}. {}", x, y);
-                    changed = true;
+    // Advance one round, returning how many seats changed.
+    fn step(&mut self, threads: usize) -> usize {
+        let changed: Vec<usize> = if threads > 1 && self.frontier.len() >= 2 * MIN_SEATS_PER_THREAD {
+            let chunk = max(MIN_SEATS_PER_THREAD, self.frontier.len().div_ceil(threads));
+            let sim = &*self;
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = sim.frontier.chunks(chunk)
+                    .map(|seats| scope.spawn(move || sim.changed_in(seats)))
+                    .collect();
+                handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+            })
+        }
+        else {
+            self.changed_in(&self.frontier)
+        };
+
+        // Apply the changes and work out which seats to look at next round.
+        for seat in self.frontier.drain(..) {
+            self.in_frontier[seat] = false;
+        }
+        for seat in &changed {
+            self.occupied[*seat] = !self.occupied[*seat];
+            self.num_occupied += if self.occupied[*seat] { 1 } else { -1 };
+
+            for s in std::iter::once(seat).chain(self.watchers[*seat].iter()) {
+                if !self.in_frontier[*s] {
+                    self.in_frontier[*s] = true;
+                    self.frontier.push(*s);
                 }
             }
-
-            // self.show_seating(after);
-            // println!("{} occupied.", num_occupied);
-            // println!();
         }
 
-        return num_occupied;
+        return changed.len();
     }
 }
 
 impl super::Day for SeatingSystem {
 
     fn part1(&self) -> Result<i64, &str> {
-        return Ok(self.final_occupied(4,  1) as i64);
+        return Ok(self.final_occupied(&Rule::puzzle(4, Neighborhood::Adjacent)) as i64);
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        return Ok(self.final_occupied(5,  max(self.dim_x, self.dim_y)) as i64);
+        return Ok(self.final_occupied(&Rule::puzzle(5, Neighborhood::LineOfSight(max(self.dim_x, self.dim_y)))) as i64);
     }
 }
 
@@ -213,6 +469,113 @@ mod tests {
         assert_eq!(day.part2(), Ok(26));
     }
 
+    #[test]
+    fn test_rule() {
+        let rule = Rule::parse("B0/S0123", Neighborhood::Adjacent).unwrap();
+        assert_eq!(rule, Rule::puzzle(4, Neighborhood::Adjacent));
+        assert!(rule.next(false, 0));
+        assert!(!rule.next(false, 1));
+        assert!(rule.next(true, 3));
+        assert!(!rule.next(true, 4));
+        assert!(Rule::parse("B0/S9", Neighborhood::Adjacent).is_err());
+        assert!(Rule::parse("X0", Neighborhood::Adjacent).is_err());
+        assert_eq!(Rule::new(&[9], &[], Neighborhood::Adjacent).err(), Some(String::from("invalid neighbor count 9")));
+        assert_eq!(Rule::new(&[0], &[0, 1, 2, 3], Neighborhood::Adjacent), Ok(rule));
+        assert_eq!(Rule::puzzle(20, Neighborhood::Adjacent), Rule::parse("B0/S012345678", Neighborhood::Adjacent).unwrap());
+    }
+
+    #[test]
+    fn test_neighborhoods() {
+        let day = &SeatingSystem::load("data/day11_example1.txt");
+        let adjacent = day.compute_neighbors(Neighborhood::Adjacent);
+        assert_eq!(adjacent[&(0, 0)].len(), 2);
+        let wrapping = day.compute_neighbors(Neighborhood::Wrapping(1));
+        assert_eq!(wrapping[&(0, 0)].len(), 6);
+
+        // Trailing floor counts towards the room's size, and the seat reached
+        // both ways round is only one neighbor.
+        let day = &SeatingSystem::load("data/day11_wrap.txt");
+        assert_eq!((day.dim_x, day.dim_y), (5, 2));
+        let wrapping = day.compute_neighbors(Neighborhood::Wrapping(5));
+        assert_eq!(wrapping[&(0, 0)], vec![(2, 0)]);
+        assert_eq!(wrapping[&(2, 0)], vec![(0, 0)]);
+        assert_eq!(day.render_text(&day.record(&Rule::puzzle(4, Neighborhood::Adjacent), 1).1.frames[0]),
+                   "L.L..\n.....\n");
+    }
+
+    #[test]
+    fn ex1_custom_rule() {
+        // Same as part 1 but with a wrapping neighborhood.
+        let day = &SeatingSystem::load("data/day11_example1.txt");
+        let rule = Rule::puzzle(4, Neighborhood::Wrapping(1));
+        let occupied = day.final_occupied(&rule);
+        assert_eq!(occupied, 30);
+
+        // Every seat fills and stays full whatever its neighbors do.
+        let rule = Rule::parse("B012345678/S012345678", Neighborhood::Adjacent).unwrap();
+        assert_eq!(day.final_occupied(&rule), 71);
+    }
+
+    #[test]
+    fn ex1_changes() {
+        let day = &SeatingSystem::load("data/day11_example1.txt");
+        let outcome = day.simulate(&Rule::puzzle(4, Neighborhood::Adjacent), 1);
+        assert_eq!(outcome.occupied, 37);
+        assert_eq!(outcome.rounds, 5);
+        assert_eq!(outcome.changes[0], 71);
+        assert_eq!(outcome.changes.len(), outcome.rounds);
+    }
+
+    #[test]
+    fn ex1_cycle() {
+        // Every seat fills when it can and empties straight away: the room blinks.
+        let day = &SeatingSystem::load("data/day11_example1.txt");
+        let rule = Rule::parse("B0/S", Neighborhood::Adjacent).unwrap();
+        let outcome = day.simulate(&rule, 1);
+        assert_eq!(outcome.cycle, Some(Cycle { first_repeat: 2, period: 2 }));
+        assert_eq!(outcome.changes, vec![71, 71]);
+
+        let outcome = day.simulate(&Rule::puzzle(4, Neighborhood::Adjacent), 1);
+        assert_eq!(outcome.cycle, None);
+    }
+
+    #[test]
+    fn ex1_frames() {
+        let day = &SeatingSystem::load("data/day11_example1.txt");
+        let (outcome, recording) = day.record(&Rule::puzzle(4, Neighborhood::Adjacent), 1);
+        assert_eq!(recording.frames.len(), outcome.rounds + 1);
+
+        let first = day.render_text(&recording.frames[0]);
+        assert_eq!(first.lines().next(), Some("L.LL.LL.LL"));
+        let second = day.render_text(&recording.frames[1]);
+        assert_eq!(second.lines().next(), Some("#.##.##.##"));
+        let third = day.render_text(&recording.frames[2]);
+        assert_eq!(third.lines().next(), Some("#.LL.L#.##"));
+        assert_eq!(third.lines().nth(1), Some("#LLLLLL.L#"));
+
+        let ppm = day.render_ppm(&recording.frames[0], 2);
+        let header = b"P6\n20 20\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 20 * 20 * 3);
+
+        let dir = std::env::temp_dir().join("rs_aoc2020_day11_frames");
+        fs::create_dir_all(&dir).unwrap();
+        recording.write_text(&dir).unwrap();
+        recording.write_ppm(&dir, 1).unwrap();
+        let last = format!("frame_{:04}.txt", outcome.rounds);
+        assert_eq!(fs::read_to_string(dir.join(last)).unwrap(),
+                   day.render_text(recording.frames.last().unwrap()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn threaded() {
+        let day = &SeatingSystem::load("data/day11_input.txt");
+        let rule = &Rule::puzzle(5, Neighborhood::LineOfSight(max(day.dim_x, day.dim_y)));
+        assert_eq!(day.simulate(rule, 4), day.simulate(rule, 1));
+        assert_eq!(day.simulate(rule, 4).occupied, 2197);
+    }
+
     #[test]
     fn part1() {
         let day = &SeatingSystem::load("data/day11_input.txt");
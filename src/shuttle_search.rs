@@ -3,6 +3,15 @@ use std::io::BufReader;
 use std::io::BufRead;
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::convert::TryFrom;
+use crate::number_theory;
+
+// A bus leaving at a time.  Orders by time, then bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Departure {
+    pub time: i64,
+    pub bus: i64,
+}
 
 pub struct ShuttleSearch {
     t: i64,
@@ -10,7 +19,7 @@ pub struct ShuttleSearch {
 }
 
 impl ShuttleSearch {
-    pub fn load(filename: &str) -> ShuttleSearch {
+    pub fn load(filename: &str) -> Result<ShuttleSearch, String> {
         lazy_static! {
             static ref RE: Regex = Regex::new("(x|[0-9]+)").unwrap();
         }
@@ -37,14 +46,17 @@ impl ShuttleSearch {
                 }
                 _ => {
                     // Interpret as integer bus number
-                    let bus_no: i64 = cap[1].parse().unwrap();
+                    let bus_no: i64 = match cap[1].parse() {
+                        Ok(bus_no) if bus_no > 0 => bus_no,
+                        _ => return Err(format!("bus {} at position {} must be a positive number", &cap[1], index)),
+                    };
                     buses.push((bus_no, index));
                 }
             }
             index += 1;
         }
 
-        return ShuttleSearch { t: t, buses: buses }
+        return Ok(ShuttleSearch { t: t, buses: buses });
     }
 
     // Figure out which bus is next, return wait, bus_no.
@@ -66,42 +78,56 @@ impl ShuttleSearch {
         return (next_arrival_t, next_arrival_bus);
     }
 
-    fn alignment_time(&self) -> i64 {
-        let mut t: i64 = -1;
-        let mut t_step: i64 = 1;
-        let mut not_satisfied: Vec<(i64, i64)> = vec![];
-
-        // Load all buses into not_satisfied
-        not_satisfied.clear();
-        for (bus, bus_offset) in &self.buses {
-            not_satisfied.push( (*bus, (*bus - *bus_offset).rem_euclid(*bus)) );
+    // All departures in the window [from, to], sorted by time then bus.
+    pub fn departures(&self, from: i64, to: i64) -> Vec<Departure> {
+        let mut departures: Vec<Departure> = Vec::new();
+
+        for (bus, _) in &self.buses {
+            // First multiple of bus at or after from, then every bus minutes until past to
+            // (or past the largest time there is).
+            let mut t = from.checked_add((*bus - from.rem_euclid(*bus)) % *bus);
+            while let Some(time) = t.filter(|time| *time <= to) {
+                departures.push(Departure { time: time, bus: *bus });
+                t = time.checked_add(*bus);
+            }
         }
+        departures.sort();
 
-        while not_satisfied.len() > 0 {
-            t += t_step;
-
-            let mut to_remove: Vec<usize> = Vec::new();
+        return departures;
+    }
 
-            // Check to see if any not_satisfied buses are now satisfied.
-            let mut i = 0;
-            for (bus, remainder) in &not_satisfied {
-                if (t % *bus) == *remainder {
-                    to_remove.push(i);
-                    t_step *= *bus;
-                    // println!("Satisfied {}, {} at {}, step now {}", *bus, *remainder, t, t_step);
-                }
-                i += 1;
+    // Earliest departure of each bus at or after t, in schedule order.  Buses whose
+    // next departure is past the largest time there is are left out.
+    pub fn next_departures(&self, t: i64) -> Vec<Departure> {
+        let mut departures: Vec<Departure> = Vec::new();
+        for (bus, _) in &self.buses {
+            if let Some(time) = t.checked_add((*bus - t.rem_euclid(*bus)) % *bus) {
+                departures.push(Departure { time: time, bus: *bus });
             }
+        }
 
-            // remove newly satisfied buses from not_satisfied
-            while to_remove.len() > 0 {
-                let i = to_remove.pop().unwrap();
-                not_satisfied.remove(i);
-            }
+        return departures;
+    }
 
+    // Earliest t >= 0 at which each given bus departs at t + its offset.
+    pub fn earliest_alignment(schedule: &[(i64, i64)]) -> Result<i128, &'static str> {
+        let mut constraints: Vec<(i128, i128)> = Vec::new();
+        for (bus, bus_offset) in schedule {
+            if *bus <= 0 {
+                return Err("bus numbers must be positive");
+            }
+            let bus = *bus as i128;
+            constraints.push( ((-(*bus_offset as i128)).rem_euclid(bus), bus) );
         }
 
-        return t;
+        let (t, _period) = number_theory::crt(&constraints)?;
+
+        return Ok(t);
+    }
+
+    // Earliest t at which every bus departs at its position in the list.
+    fn alignment_time(&self) -> Result<i128, &'static str> {
+        return ShuttleSearch::earliest_alignment(&self.buses);
     }
 }
 
@@ -113,7 +139,9 @@ impl super::Day for ShuttleSearch {
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        return Ok(self.alignment_time());
+        let t = self.alignment_time()?;
+
+        return i64::try_from(t).map_err(|_| "overflow");
     }
 }
 
@@ -124,7 +152,7 @@ mod tests {
 
     #[test]
     fn test_load() {
-        let ss = &ShuttleSearch::load("data/day13_example1.txt");
+        let ss = &ShuttleSearch::load("data/day13_example1.txt").unwrap();
         assert_eq!(ss.t, 939);
         assert_eq!(ss.buses.len(), 5);
         assert_eq!(ss.buses[0].0, 7);  // First entry: Bus 7
@@ -135,55 +163,94 @@ mod tests {
 
     #[test]
     fn test_ex1_part1() {
-        let ss =  &ShuttleSearch::load("data/day13_example1.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example1.txt").unwrap();
         assert_eq!(ss.part1(), Ok(295));
     }
 
     #[test]
     fn test_part1() {
-        let ss =  &ShuttleSearch::load("data/day13_input.txt");
+        let ss =  &ShuttleSearch::load("data/day13_input.txt").unwrap();
         assert_eq!(ss.part1(), Ok(2545));
     }
 
     #[test]
     fn test_part2_ex1() {
-        let ss =  &ShuttleSearch::load("data/day13_example1.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example1.txt").unwrap();
         assert_eq!(ss.part2(), Ok(1068781));
     }
 
     #[test]
     fn test_part2_ex2() {
-        let ss =  &ShuttleSearch::load("data/day13_example2.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example2.txt").unwrap();
         assert_eq!(ss.part2(), Ok(3417));
     }
 
     #[test]
     fn test_part2_ex3() {
-        let ss =  &ShuttleSearch::load("data/day13_example3.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example3.txt").unwrap();
         assert_eq!(ss.part2(), Ok(754018));
     }
 
     #[test]
     fn test_part2_ex4() {
-        let ss =  &ShuttleSearch::load("data/day13_example4.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example4.txt").unwrap();
         assert_eq!(ss.part2(), Ok(779210));
     }
 
     #[test]
     fn test_part2_ex5() {
-        let ss =  &ShuttleSearch::load("data/day13_example5.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example5.txt").unwrap();
         assert_eq!(ss.part2(), Ok(1261476));
     }
 
     #[test]
     fn test_part2_ex6() {
-        let ss =  &ShuttleSearch::load("data/day13_example6.txt");
+        let ss =  &ShuttleSearch::load("data/day13_example6.txt").unwrap();
         assert_eq!(ss.part2(), Ok(1202161486));
     }
 
+    #[test]
+    fn test_part2_non_coprime() {
+        let ss =  &ShuttleSearch::load("data/day13_example7.txt").unwrap();
+        assert_eq!(ss.part2(), Ok(4));
+
+        let ss =  &ShuttleSearch::load("data/day13_example8.txt").unwrap();
+        assert_eq!(ss.part2(), Err("no solution"));
+    }
+
+    #[test]
+    fn test_departures() {
+        let ss =  &ShuttleSearch::load("data/day13_example1.txt").unwrap();
+        assert_eq!(ss.departures(939, 945),
+                   vec![Departure { time: 944, bus: 59 }, Departure { time: 945, bus: 7 }]);
+        assert_eq!(ss.departures(0, 0).len(), 5);
+        let next: Vec<(i64, i64)> = ss.next_departures(939).iter().map(|d| (d.bus, d.time)).collect();
+        assert_eq!(next, vec![(7, 945), (13, 949), (59, 944), (31, 961), (19, 950)]);
+        assert_eq!(ss.next_departures(945)[0], Departure { time: 945, bus: 7 });
+
+        // Near the end of time, departures stop rather than overflow.  i64::MAX is a multiple of 7.
+        assert_eq!(ss.departures(i64::MAX - 3, i64::MAX), vec![Departure { time: i64::MAX, bus: 7 }]);
+        assert_eq!(ss.next_departures(i64::MAX), vec![Departure { time: i64::MAX, bus: 7 }]);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(ShuttleSearch::load("data/day13_bus0.txt").err(),
+                   Some(String::from("bus 0 at position 1 must be a positive number")));
+    }
+
+    #[test]
+    fn test_earliest_alignment() {
+        assert_eq!(ShuttleSearch::earliest_alignment(&[(17, 0), (13, 2), (19, 3)]), Ok(3417));
+        assert_eq!(ShuttleSearch::earliest_alignment(&[(7, 0), (13, 1)]), Ok(77));
+        assert_eq!(ShuttleSearch::earliest_alignment(&[(4, 0), (6, 1)]), Err("no solution"));
+        assert_eq!(ShuttleSearch::earliest_alignment(&[(0, 0)]), Err("bus numbers must be positive"));
+        assert_eq!(ShuttleSearch::earliest_alignment(&[(i64::MAX, i64::MIN)]), Ok(1));
+    }
+
     #[test]
     fn test_part2() {
-        let ss =  &ShuttleSearch::load("data/day13_input.txt");
+        let ss =  &ShuttleSearch::load("data/day13_input.txt").unwrap();
         assert_eq!(ss.part2(), Ok(266204454441577));
     }
 }
\ No newline at end of file
@@ -2,12 +2,43 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::collections::HashMap;
 use rusttype::Point;
+use crate::number_theory::gcd;
+
+// The slopes checked in part 2, as (right, down).
+const PART2_SLOPES: [(u32, u32); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+// Largest right or down step accepted from the command line.
+const MAX_STEP: u32 = 1000;
+
+// Most copies of the map render() draws side by side.
+const MAX_RENDER_REPEATS: u32 = 100;
+
+// A slope and the number of trees hit following it.
+pub type SlopeHits = (Point<u32>, i64);
+
+// Parse slopes written as "right/down" separated by commas, e.g. "3/1,1/2".
+pub fn parse_slopes(s: &str) -> Result<Vec<Point<u32>>, String> {
+    let mut slopes: Vec<Point<u32>> = Vec::new();
+    for part in s.split(',') {
+        let nums: Vec<&str> = part.trim().split('/').collect();
+        if nums.len() != 2 {
+            return Err(format!("invalid slope '{}', expected right/down", part));
+        }
+        match (nums[0].parse(), nums[1].parse()) {
+            (Ok(x), Ok(y)) if y > 0 && x <= MAX_STEP && y <= MAX_STEP => slopes.push(Point {x: x, y: y}),
+            _ => return Err(format!("invalid slope '{}', expected right/down up to {}", part, MAX_STEP)),
+        }
+    }
+
+    return Ok(slopes);
+}
 
 pub struct TobogganTrajectory {
     // If a point is in the map, it has a tree.  All entries store the value true.
     trees: HashMap<Point<u32>, bool>,
     h: u32,
     w: u32,
+    slopes: Vec<Point<u32>>,  // slopes multiplied together in part 2
 }
 
 impl TobogganTrajectory {
@@ -45,7 +76,18 @@ impl TobogganTrajectory {
             y += 1;
         }
 
-        return TobogganTrajectory {trees: tree_map, h: max_y+1, w: max_x+1 }
+        let slopes = PART2_SLOPES.iter().map(|(x, y)| Point {x: *x, y: *y}).collect();
+
+        return TobogganTrajectory {trees: tree_map, h: max_y+1, w: max_x+1, slopes: slopes }
+    }
+
+    // Use these slopes in part 2 instead of the puzzle's five.
+    pub fn with_slopes(mut self, slopes: Vec<Point<u32>>) -> Result<TobogganTrajectory, String> {
+        if let Some(slope) = slopes.iter().find(|slope| slope.y == 0) {
+            return Err(format!("slope {}/{} must move down", slope.x, slope.y));
+        }
+        self.slopes = slopes;
+        return Ok(self);
     }
 
     fn tree_hits(&self, slope: &Point<u32>) -> i64 {
@@ -53,15 +95,86 @@ impl TobogganTrajectory {
         let mut hits = 0;
 
         while position.y < self.h {
-            let wrapped_x = position.x % self.w;
-            if self.trees.contains_key(&Point {x: wrapped_x, y:position.y}) {
+            if self.trees.contains_key(&position) {
                 hits += 1;
             }
-            position.x += slope.x;
-            position.y += slope.y;
+            // Keep x on the map so large slopes can't overflow.
+            position.x = (position.x + slope.x % self.w) % self.w;
+            match position.y.checked_add(slope.y) {
+                Some(y) => position.y = y,
+                None => break,
+            }
         }
         return hits;
     }
+
+    // Tree hits for every distinct slope right/down with right <= bound and 1 <= down <= bound,
+    // in order of increasing hits.  Slopes that are multiples of another (2/2) are skipped.
+    pub fn search_slopes(&self, bound: u32) -> Vec<SlopeHits> {
+        let mut results: Vec<SlopeHits> = Vec::new();
+        for y in 1..bound+1 {
+            for x in 0..bound+1 {
+                if gcd(x as i128, y as i128) == 1 {
+                    let slope = Point {x: x, y: y};
+                    results.push((slope, self.tree_hits(&slope)));
+                }
+            }
+        }
+        results.sort_by_key(|(slope, hits)| (*hits, slope.y, slope.x));
+
+        return results;
+    }
+
+    // The slopes (within the bound) hitting the fewest and the most trees.
+    pub fn least_and_most(&self, bound: u32) -> Option<(SlopeHits, SlopeHits)> {
+        let results = self.search_slopes(bound);
+        if results.is_empty() {
+            return None;
+        }
+        return Some((results[0], results[results.len()-1]));
+    }
+
+    // Draw the map with the path marked: 'O' for open squares landed on, 'X' for trees hit.
+    // The map is repeated to the right as far as the path goes, up to MAX_RENDER_REPEATS
+    // copies; a path wider than that wraps round like tree_hits().
+    pub fn render(&self, slope: &Point<u32>) -> Result<String, String> {
+        if slope.y == 0 {
+            return Err(String::from("slope must move down"));
+        }
+
+        let landings = ((self.h - 1) / slope.y + 1) as u64;
+        let needed = (landings - 1) * slope.x as u64 / self.w as u64 + 1;
+        let repeats = if needed > MAX_RENDER_REPEATS as u64 { 1 } else { needed as u32 };
+        let width = self.w * repeats;
+
+        let mut path: HashMap<Point<u32>, bool> = HashMap::new();
+        let mut position = Point {x: 0, y: 0};
+        while position.y < self.h {
+            path.insert(position, true);
+            position.x = (position.x + slope.x % width) % width;
+            match position.y.checked_add(slope.y) {
+                Some(y) => position.y = y,
+                None => break,
+            }
+        }
+
+        let mut map = String::new();
+        for y in 0..self.h {
+            for x in 0..width {
+                let tree = self.trees.contains_key(&Point {x: x % self.w, y: y});
+                let c = match (path.contains_key(&Point {x: x, y: y}), tree) {
+                    (true, true) => 'X',
+                    (true, false) => 'O',
+                    (false, true) => '#',
+                    (false, false) => '.',
+                };
+                map.push(c);
+            }
+            map.push('\n');
+        }
+
+        return Ok(map);
+    }
 }
 
 impl super::Day for TobogganTrajectory {
@@ -72,15 +185,7 @@ impl super::Day for TobogganTrajectory {
     }
 
     fn part2(&self) -> Result<i64, &str> {
-        let slopes: [Point<u32>; 5] = [
-            Point {x:1, y:1},
-            Point {x:3, y:1},
-            Point {x:5, y:1},
-            Point {x:7, y:1},
-            Point {x:1, y:2},
-            ];
-
-        return Ok(slopes.iter().map(|slope| self.tree_hits(slope)).product());
+        return Ok(self.slopes.iter().map(|slope| self.tree_hits(slope)).product());
     }
 }
 
@@ -89,6 +194,70 @@ mod tests {
     use super::*;
     use crate::Day;
 
+    #[test]
+    fn test_examples() {
+        let tt = &TobogganTrajectory::load("data/day3_example1.txt");
+
+        assert_eq!(tt.part1(), Ok(7));
+        assert_eq!(tt.part2(), Ok(336));
+    }
+
+    #[test]
+    fn test_slopes() {
+        assert_eq!(parse_slopes("3/1, 1/2").unwrap(), vec![Point {x: 3, y: 1}, Point {x: 1, y: 2}]);
+        assert!(parse_slopes("3").is_err());
+        assert!(parse_slopes("3/0").is_err());
+        assert!(parse_slopes("1001/1").is_err());
+        assert!(parse_slopes("4294967295/1").is_err());
+
+        let tt = TobogganTrajectory::load("data/day3_example1.txt")
+            .with_slopes(parse_slopes("3/1,7/1").unwrap()).unwrap();
+        assert_eq!(tt.part2(), Ok(7 * 4));
+
+        let slopes = vec![Point {x: 3, y: 1}, Point {x: 1, y: 0}];
+        assert_eq!(TobogganTrajectory::load("data/day3_example1.txt").with_slopes(slopes).err(),
+                   Some(String::from("slope 1/0 must move down")));
+    }
+
+    #[test]
+    fn test_search_slopes() {
+        let tt = &TobogganTrajectory::load("data/day3_example1.txt");
+
+        let results = tt.search_slopes(3);
+        assert_eq!(results.len(), 8);
+        assert!(!results.iter().any(|(slope, _)| *slope == Point {x: 2, y: 2}));
+
+        let (least, most) = tt.least_and_most(7).unwrap();
+        assert_eq!(least, (Point {x: 5, y: 2}, 0));
+        assert_eq!(most, (Point {x: 3, y: 1}, 7));
+        assert_eq!(tt.tree_hits(&most.0), most.1);
+
+        // Huge steps stay on the map instead of overflowing.
+        assert_eq!(tt.tree_hits(&Point {x: u32::MAX, y: u32::MAX}), 0);
+        assert_eq!(tt.tree_hits(&Point {x: u32::MAX, y: 1}), tt.tree_hits(&Point {x: u32::MAX % 11, y: 1}));
+    }
+
+    #[test]
+    fn test_render() {
+        let tt = &TobogganTrajectory::load("data/day3_example1.txt");
+        let map = tt.render(&Point {x: 3, y: 1}).unwrap();
+        let lines: Vec<&str> = map.lines().collect();
+
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "O.##.........##.........##.......");
+        assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+        assert_eq!(lines[2], ".#....X..#..#....#..#..#....#..#.");
+        assert_eq!(lines[10], ".#..#...#.#.#..#...#.#.#..#...X.#");
+        assert_eq!(map.matches('X').count(), 7);
+
+        // Too wide to repeat the map for, so the path wraps onto one copy.
+        let map = tt.render(&Point {x: u32::MAX, y: 1}).unwrap();
+        assert_eq!(map.lines().next().map(|l| l.len()), Some(11));
+        assert_eq!(map.matches(&['O', 'X'][..]).count(), 11);
+        assert_eq!(map.matches('X').count() as i64, tt.tree_hits(&Point {x: u32::MAX, y: 1}));
+        assert!(tt.render(&Point {x: 1, y: 0}).is_err());
+    }
+
     #[test]
     fn test_part1() {
         let tt = &TobogganTrajectory::load("data/day3_input.txt");
@@ -0,0 +1,44 @@
+// Run each day's example through the library's Day trait.
+
+use rs_aoc2020::Day;
+use rs_aoc2020::report_repair::ReportRepair;
+use rs_aoc2020::password_philosophy::PasswordPhilosophy;
+use rs_aoc2020::toboggan_trajectory::TobogganTrajectory;
+use rs_aoc2020::custom_customs::Customs;
+use rs_aoc2020::handy_haversacks::Haversacks;
+use rs_aoc2020::handheld_halting::Halting;
+use rs_aoc2020::encoding_error::EncodingError;
+use rs_aoc2020::adapter_array::AdapterArray;
+use rs_aoc2020::seating_system::SeatingSystem;
+use rs_aoc2020::rain_risk::RainRisk;
+use rs_aoc2020::shuttle_search::ShuttleSearch;
+
+fn check(day: &dyn Day, part1: i64, part2: i64) {
+    assert_eq!(day.part1(), Ok(part1));
+    assert_eq!(day.part2(), Ok(part2));
+}
+
+#[test]
+fn test_examples() {
+    check(&ReportRepair::load("data/day1_example1.txt"), 514579, 241861950);
+    check(&PasswordPhilosophy::load("data/day2_example.txt").unwrap(), 2, 1);
+    check(&TobogganTrajectory::load("data/day3_example1.txt"), 7, 336);
+    check(&Customs::load("data/day6_example1.txt"), 11, 6);
+    check(&Haversacks::load("data/day7_example1.txt"), 4, 32);
+    check(&Halting::load("data/day8_example1.txt"), 5, 8);
+    check(&EncodingError::load("data/day9_example1.txt", 5), 127, 62);
+    check(&AdapterArray::load("data/day10_example1.txt"), 35, 8);
+    check(&SeatingSystem::load("data/day11_example1.txt"), 37, 26);
+    check(&RainRisk::load("data/day12_example1.txt").unwrap(), 25, 286);
+    check(&ShuttleSearch::load("data/day13_example1.txt").unwrap(), 295, 1068781);
+}
+
+#[test]
+fn test_days_as_trait_objects() {
+    let days: Vec<Box<dyn Day>> = vec![
+        Box::new(ReportRepair::load("data/day1_example1.txt")),
+        Box::new(AdapterArray::load("data/day10_example2.txt")),
+    ];
+    let answers: Vec<(i64, i64)> = days.iter().map(|d| (d.part1().unwrap(), d.part2().unwrap())).collect();
+    assert_eq!(answers, vec![(514579, 241861950), (220, 19208)]);
+}
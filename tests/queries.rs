@@ -0,0 +1,70 @@
+// Queries beyond the puzzle answers, used through the library's public API.
+
+use rs_aoc2020::Day;
+use rs_aoc2020::adapter_array::{AdapterArray, ChainError, JoltageRules};
+use rs_aoc2020::binary_boarding::{BoardingPass, Plane};
+use rs_aoc2020::encoding_error::{self, EncodingError};
+use rs_aoc2020::handheld_halting::{Halting, Processor};
+use rs_aoc2020::handy_haversacks::Haversacks;
+use rs_aoc2020::number_theory;
+
+#[test]
+fn test_encoding_error() {
+    let ee = EncodingError::load("data/day9_example1.txt", 5);
+    assert_eq!(ee.all_invalid()[0], (14, 127));
+    assert_eq!(ee.ranges_summing_to(127), vec![(2, 5)]);
+
+    let invalid: Vec<(usize, i64)> = encoding_error::invalid_values(vec![1, 2, 3, 4, 7], 2).collect();
+    assert_eq!(invalid, vec![(3, 4)]);
+}
+
+#[test]
+fn test_adapter_array() {
+    let aa = AdapterArray::load("data/day10_gap.txt");
+    assert_eq!(aa.validate(), Err(ChainError { from: 5, to: 9 }));
+
+    let aa = AdapterArray::load("data/day10_example1.txt").with_rules(JoltageRules::new(&[1, 3], 3).unwrap());
+    assert_eq!(aa.arrangements_iter().count(), 2);
+    assert_eq!(aa.part2(), Ok(2));
+}
+
+#[test]
+fn test_processor() {
+    let halting = Halting::load("data/day8_example1.txt");
+    let mut p = Processor::new();
+    assert!(p.run(halting.program()).is_err());
+    assert_eq!(p.acc(), 5);
+
+    p.reset();
+    p.patch(7, "nop");
+    assert_eq!(p.run(halting.program()), Ok(8));
+
+    let mut counter = Halting::new();
+    counter.store(0, "acc", 3);
+    counter.store(1, "jmp", 2);
+    counter.store(2, "acc", 100);
+    counter.store(3, "acc", -1);
+    let mut p = Processor::new();
+    assert_eq!(p.run(counter.program()), Ok(2));
+}
+
+#[test]
+fn test_bag_graph() {
+    let hs = Haversacks::load("data/day7_example1.txt");
+    let chains = hs.graph().chains("light red", "shiny gold");
+    assert_eq!(chains.len(), 2);
+    assert!(hs.graph().find_cycle().is_none());
+}
+
+#[test]
+fn test_boarding_pass() {
+    let pass = BoardingPass::decode("BFFFBBFRRR", Plane::STANDARD).unwrap();
+    assert_eq!(pass.id(), 567);
+    assert_eq!(BoardingPass::from_id(567, Plane::STANDARD).unwrap().encode(), "BFFFBBFRRR");
+}
+
+#[test]
+fn test_number_theory() {
+    assert_eq!(number_theory::crt(&[(0, 3), (3, 4), (4, 5)]), Ok((39, 60)));
+    assert_eq!(number_theory::mod_inverse(3, 7), Some(5));
+}